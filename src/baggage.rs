@@ -0,0 +1,452 @@
+//! Helpers to read and set W3C Baggage entries of the current request.
+//!
+//! Baggage set with these helpers is visible to [`current`] and [`get`], is inherited by
+//! spans started in the same context, and is injected into outbound requests by the
+//! instrumented HTTP clients and AWS SDK operations.
+//!
+//! # Example
+//!
+//! ```rust
+//! use telemetry_rust::{KeyValue, baggage};
+//!
+//! let _guard = baggage::attach([KeyValue::new("tenant.id", "acme")]);
+//!
+//! assert!(baggage::get("tenant.id").is_some_and(|value| value.as_str() == "acme"));
+//! ```
+
+use opentelemetry::{
+    Context, ContextGuard, StringValue,
+    baggage::{Baggage, BaggageExt, KeyValueMetadata},
+    propagation::{
+        Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter,
+    },
+};
+use percent_encoding::{
+    AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode,
+};
+use std::sync::LazyLock;
+use tracing::Span;
+use tracing_opentelemetry::{OpenTelemetrySpanExt, SetParentError};
+
+const BAGGAGE_HEADER: &str = "baggage";
+const VALUE_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'.')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'~');
+
+static BAGGAGE_FIELDS: LazyLock<[String; 1]> =
+    LazyLock::new(|| [BAGGAGE_HEADER.to_owned()]);
+
+/// Returns the baggage of the current context.
+///
+/// Entries attached to the current OpenTelemetry context, e.g. with [`attach`] or
+/// [`BaggageFutureExt::with_baggage`], take precedence over entries of the current
+/// tracing span.
+pub fn current() -> Baggage {
+    clone_baggage(current_context().baggage())
+}
+
+/// Returns the value of a baggage entry of the current context.
+pub fn get(key: impl AsRef<str>) -> Option<StringValue> {
+    current_context().baggage().get(key).cloned()
+}
+
+/// Returns the current context with the given entries added to its baggage.
+///
+/// Entries with invalid keys, or exceeding the limits of [`Baggage`], are ignored.
+pub fn context_with<I>(entries: I) -> Context
+where
+    I: IntoIterator,
+    I::Item: Into<KeyValueMetadata>,
+{
+    with_entries(&current_context(), entries)
+}
+
+/// Attaches the current context with the given entries added to its baggage, until the
+/// returned guard is dropped.
+///
+/// The guard must not be held across `.await` points, use
+/// [`BaggageFutureExt::with_baggage`] to set baggage for an async block instead.
+pub fn attach<I>(entries: I) -> ContextGuard
+where
+    I: IntoIterator,
+    I::Item: Into<KeyValueMetadata>,
+{
+    context_with(entries).attach()
+}
+
+/// Extension trait to set baggage of [`tracing::Span`]s.
+pub trait BaggageSpanExt {
+    /// Adds entries to the baggage of a span not started yet, and of all its children.
+    ///
+    /// Like [`OpenTelemetrySpanExt::set_parent`], this only works before the span is
+    /// entered or its context is read, and it sets the current context as the parent
+    /// of the span.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use telemetry_rust::{KeyValue, baggage::BaggageSpanExt};
+    ///
+    /// let span = tracing::info_span!("checkout");
+    /// let _ = span.set_baggage([KeyValue::new("tenant.id", "acme")]);
+    /// ```
+    fn set_baggage<I>(&self, entries: I) -> Result<(), SetParentError>
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyValueMetadata>;
+}
+
+impl BaggageSpanExt for Span {
+    fn set_baggage<I>(&self, entries: I) -> Result<(), SetParentError>
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyValueMetadata>,
+    {
+        self.set_parent(context_with(entries))
+    }
+}
+
+#[cfg(feature = "future")]
+pin_project_lite::pin_project! {
+    /// Future with baggage entries attached to the current context while it is polled.
+    ///
+    /// Created by [`BaggageFutureExt::with_baggage`].
+    pub struct WithBaggage<F> {
+        #[pin]
+        inner: F,
+        baggage: Baggage,
+    }
+}
+
+#[cfg(feature = "future")]
+impl<F: std::future::Future> std::future::Future for WithBaggage<F> {
+    type Output = F::Output;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = self.project();
+        let _guard =
+            Context::map_current(|current| merge(current, this.baggage)).attach();
+        this.inner.poll(cx)
+    }
+}
+
+/// Extension trait to set baggage for futures.
+#[cfg(feature = "future")]
+pub trait BaggageFutureExt: std::future::Future + Sized {
+    /// Adds entries to the baggage of the current context while the future is polled.
+    ///
+    /// The entries are added on each poll, on top of the context current at that time,
+    /// so the future can be [`Instrument`](tracing::Instrument)ed with a span: spans
+    /// started by the future inherit both the span and the baggage.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use telemetry_rust::{KeyValue, baggage::BaggageFutureExt};
+    /// use tracing::Instrument;
+    ///
+    /// # async fn checkout() {}
+    /// # async fn example() {
+    /// checkout()
+    ///     .with_baggage([KeyValue::new("tenant.id", "acme")])
+    ///     .instrument(tracing::info_span!("checkout"))
+    ///     .await;
+    /// # }
+    /// ```
+    fn with_baggage<I>(self, entries: I) -> WithBaggage<Self>
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyValueMetadata>,
+    {
+        WithBaggage {
+            inner: self,
+            baggage: to_baggage(entries),
+        }
+    }
+}
+
+#[cfg(feature = "future")]
+impl<F: std::future::Future> BaggageFutureExt for F {}
+
+/// Size limits of the `baggage` header.
+///
+/// The defaults follow the [W3C Baggage limits](https://www.w3.org/TR/baggage/#limits):
+/// 180 entries and 8192 bytes. Note that [`Baggage`] itself stores at most 64 entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaggageLimits {
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl BaggageLimits {
+    /// Limits of the W3C Baggage specification.
+    pub const W3C: Self = Self {
+        max_entries: 180,
+        max_bytes: 8192,
+    };
+
+    /// Sets the maximum number of entries.
+    pub fn max_entries(self, max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            ..self
+        }
+    }
+
+    /// Sets the maximum size of the header value in bytes.
+    pub fn max_bytes(self, max_bytes: usize) -> Self {
+        Self { max_bytes, ..self }
+    }
+}
+
+impl Default for BaggageLimits {
+    fn default() -> Self {
+        Self::W3C
+    }
+}
+
+/// W3C Baggage propagator enforcing [`BaggageLimits`].
+///
+/// Entries are injected and extracted with their metadata (properties), e.g.
+/// `tenant.id=acme;ttl=60`. Entries exceeding the limits are dropped whole, so an
+/// oversized header never gets truncated in the middle of an entry.
+///
+/// This propagator is used for `baggage` in `OTEL_PROPAGATORS`.
+#[derive(Debug, Clone, Default)]
+pub struct BaggagePropagator {
+    limits: BaggageLimits,
+}
+
+impl BaggagePropagator {
+    /// Creates a propagator with the W3C limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a propagator with custom limits.
+    pub fn with_limits(limits: BaggageLimits) -> Self {
+        Self { limits }
+    }
+
+    fn fits(&self, entries: usize, bytes: usize) -> bool {
+        entries <= self.limits.max_entries && bytes <= self.limits.max_bytes
+    }
+}
+
+impl TextMapPropagator for BaggagePropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let (mut header, mut entries) = (String::new(), 0);
+        for (key, (value, metadata)) in cx.baggage() {
+            let mut entry = format!(
+                "{key}={}",
+                utf8_percent_encode(value.as_str(), VALUE_ENCODE_SET)
+            );
+            if !metadata.as_str().is_empty() {
+                entry.push(';');
+                entry.push_str(metadata.as_str());
+            }
+            let separator = usize::from(!header.is_empty());
+            if !self.fits(entries + 1, header.len() + separator + entry.len()) {
+                continue;
+            }
+            if separator > 0 {
+                header.push(',');
+            }
+            header.push_str(&entry);
+            entries += 1;
+        }
+        if !header.is_empty() {
+            injector.set(BAGGAGE_HEADER, header);
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let Some(headers) = extractor.get_all(BAGGAGE_HEADER) else {
+            return cx.clone();
+        };
+        let (mut entries, mut bytes) = (Vec::new(), 0);
+        for member in headers.iter().flat_map(|header| header.split(',')) {
+            let member = member.trim();
+            let (key_value, metadata) = member.split_once(';').unwrap_or((member, ""));
+            let Some((key, value)) = key_value.split_once('=') else {
+                continue;
+            };
+            let Ok(value) = percent_decode_str(value.trim()).decode_utf8() else {
+                continue;
+            };
+            let separator = usize::from(bytes > 0);
+            if !self.fits(entries.len() + 1, bytes + separator + member.len()) {
+                break;
+            }
+            bytes += separator + member.len();
+            entries.push(KeyValueMetadata::new(
+                key.trim().to_owned(),
+                value.into_owned(),
+                metadata.trim(),
+            ));
+        }
+        if entries.is_empty() {
+            return cx.clone();
+        }
+        with_entries(cx, entries)
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(BAGGAGE_FIELDS.as_ref())
+    }
+}
+
+/// Returns the context of the current span, with the baggage of the current context.
+///
+/// Outbound instrumentations use it as the parent context of client spans, so that
+/// baggage attached to the current context is propagated too.
+pub(crate) fn current_context() -> Context {
+    let span_context = Span::current().context();
+    Context::map_current(|cx| {
+        if cx.baggage().is_empty() {
+            span_context
+        } else {
+            merge(&span_context, cx.baggage())
+        }
+    })
+}
+
+fn with_entries<I>(cx: &Context, entries: I) -> Context
+where
+    I: IntoIterator,
+    I::Item: Into<KeyValueMetadata>,
+{
+    merge(cx, &to_baggage(entries))
+}
+
+fn to_baggage<I>(entries: I) -> Baggage
+where
+    I: IntoIterator,
+    I::Item: Into<KeyValueMetadata>,
+{
+    entries.into_iter().map(Into::into).collect()
+}
+
+/// Returns the context with entries added to its baggage, replacing existing ones.
+fn merge(cx: &Context, entries: &Baggage) -> Context {
+    let mut baggage = clone_baggage(cx.baggage());
+    for (key, (value, metadata)) in entries {
+        baggage.insert_with_metadata(key.clone(), value.clone(), metadata.clone());
+    }
+    cx.with_baggage(baggage)
+}
+
+fn clone_baggage(baggage: &Baggage) -> Baggage {
+    baggage
+        .iter()
+        .map(|(key, (value, metadata))| {
+            KeyValueMetadata::new(key.clone(), value.clone(), metadata.clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::KeyValue;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_attach_and_get() {
+        assert!(get("tenant.id").is_none());
+        {
+            let _guard = attach([KeyValue::new("tenant.id", "acme")]);
+            let _inner = attach([KeyValueMetadata::new("region", "eu", "ttl=60")]);
+
+            let baggage = current();
+            assert!(baggage.len() == 2);
+            assert!(get("tenant.id") == Some("acme".into()));
+            assert!(
+                baggage.get_with_metadata("region")
+                    == Some(&("eu".into(), "ttl=60".into()))
+            );
+        }
+        assert!(current().is_empty());
+    }
+
+    #[cfg(feature = "future")]
+    #[tokio::test]
+    async fn test_future_with_baggage() {
+        use opentelemetry::trace::TracerProvider as _;
+        use opentelemetry_sdk::trace::SdkTracerProvider;
+        use tracing::Instrument;
+        use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = Registry::default()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let child_baggage = async {
+            tokio::task::yield_now().await;
+            assert!(get("tenant.id") == Some("acme".into()));
+            let child = tracing::info_span!("child");
+            clone_baggage(child.context().baggage())
+        }
+        .with_baggage([KeyValue::new("tenant.id", "acme")])
+        .instrument(tracing::info_span!("parent"))
+        .await;
+
+        assert!(child_baggage.get("tenant.id") == Some(&"acme".into()));
+        assert!(get("tenant.id").is_none());
+    }
+
+    #[test]
+    fn test_propagator_round_trip() {
+        let propagator = BaggagePropagator::new();
+        let cx = Context::new().with_baggage([
+            KeyValueMetadata::new("user", "Jane Doe", "ttl=60;secret"),
+            KeyValueMetadata::new("tenant", "acme", ""),
+        ]);
+        let mut carrier = HashMap::new();
+
+        propagator.inject_context(&cx, &mut carrier);
+        let header = &carrier[BAGGAGE_HEADER];
+        assert!(header.contains("user=Jane%20Doe;ttl=60;secret"));
+        assert!(header.contains("tenant=acme"));
+
+        let extracted = propagator.extract(&carrier);
+        let baggage = extracted.baggage();
+        assert!(baggage.len() == 2);
+        assert!(
+            baggage.get_with_metadata("user")
+                == Some(&("Jane Doe".into(), "ttl=60;secret".into()))
+        );
+        assert!(baggage.get("tenant") == Some(&"acme".into()));
+    }
+
+    #[test]
+    fn test_propagator_limits() {
+        let propagator = BaggagePropagator::with_limits(
+            BaggageLimits::W3C.max_entries(2).max_bytes(12),
+        );
+        let carrier = HashMap::from([(
+            BAGGAGE_HEADER.to_owned(),
+            "a=1, b=2 , invalid, c=3".to_owned(),
+        )]);
+
+        let cx = propagator.extract(&carrier);
+        assert!(cx.baggage().len() == 2);
+        assert!(cx.baggage().get("c").is_none());
+
+        let mut carrier = HashMap::new();
+        let cx = Context::new().with_baggage([KeyValue::new("key", "a-long-value")]);
+        propagator.inject_context(&cx, &mut carrier);
+        assert!(carrier.is_empty());
+
+        let cx = Context::new().with_baggage([KeyValue::new("a", "1")]);
+        propagator.inject_context(&cx, &mut carrier);
+        assert!(carrier[BAGGAGE_HEADER] == "a=1");
+    }
+}
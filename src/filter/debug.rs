@@ -0,0 +1,180 @@
+use http::{HeaderMap, HeaderName};
+use opentelemetry::{Context, baggage::BaggageExt, trace::TraceContextExt};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{
+    Registry,
+    registry::{LookupSpan, SpanRef},
+};
+
+/// Marker stored in span extensions of spans with per-request debug logging enabled.
+#[derive(Debug, Clone, Copy)]
+struct DebugLogging;
+
+/// Condition to enable debug logging for a single request.
+///
+/// The trigger matches a request if its extracted OpenTelemetry [`Context`] carries the
+/// configured baggage entry, or if the request has the configured header.
+/// Once matched, events inside the request span are filtered with the debug directives
+/// of the [`TracingFilter`](super::TracingFilter) instead of the global log directives.
+///
+/// Note that baggage is only extracted if the `baggage` propagator is configured
+/// (e.g. `OTEL_PROPAGATORS=tracecontext,baggage`).
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::filter::DebugTrigger;
+///
+/// let trigger = DebugTrigger::new()
+///     .baggage("debug", "true")
+///     .header(http::HeaderName::from_static("x-debug"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DebugTrigger {
+    baggage: Option<(String, String)>,
+    header: Option<HeaderName>,
+}
+
+impl DebugTrigger {
+    /// Creates a trigger that never matches until configured.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches requests carrying the baggage entry `key` with the given `value`.
+    pub fn baggage(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            baggage: Some((key.into(), value.into())),
+            ..self
+        }
+    }
+
+    /// Matches requests having the given header, regardless of its value.
+    pub fn header(self, name: HeaderName) -> Self {
+        Self {
+            header: Some(name),
+            ..self
+        }
+    }
+
+    /// Returns `true` if the request headers or its extracted context match the trigger.
+    pub fn matches(&self, headers: &HeaderMap, context: &Context) -> bool {
+        self.header
+            .as_ref()
+            .is_some_and(|name| headers.contains_key(name))
+            || self.baggage.as_ref().is_some_and(|(key, value)| {
+                context
+                    .baggage()
+                    .get(key)
+                    .is_some_and(|v| v.as_str() == value)
+            })
+    }
+}
+
+/// Enables per-request debug logging for events inside the given span.
+///
+/// Debug logging is only enabled if the span belongs to a sampled trace, so that a
+/// client cannot raise the log volume for requests that are not traced anyway.
+///
+/// Returns `true` if debug logging has been enabled.
+pub fn enable_debug_logging(span: &Span) -> bool {
+    if !span.context().span().span_context().is_sampled() {
+        return false;
+    }
+
+    span.with_subscriber(|(id, dispatch)| {
+        dispatch
+            .downcast_ref::<Registry>()
+            .and_then(|registry| registry.span(id))
+            .map(|span| span.extensions_mut().insert(DebugLogging))
+            .is_some()
+    })
+    .unwrap_or(false)
+}
+
+/// Returns `true` if debug logging is enabled for the span or any of its parents.
+pub(super) fn is_debug_logging_enabled<'a, R>(span: Option<SpanRef<'a, R>>) -> bool
+where
+    R: LookupSpan<'a>,
+{
+    span.is_some_and(|span| {
+        span.scope()
+            .any(|span| span.extensions().get::<DebugLogging>().is_some())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::{TracingFilter, parse_directives};
+    use assert2::assert;
+    use http::HeaderValue;
+    use opentelemetry::{KeyValue, trace::TracerProvider as _};
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use tracing::Level;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn set_default_subscriber(sampler: Sampler) -> tracing::subscriber::DefaultGuard {
+        let provider = SdkTracerProvider::builder().with_sampler(sampler).build();
+        let filter = TracingFilter::new(Level::INFO, Level::INFO)
+            .with_debug_directives(parse_directives("debug").unwrap());
+        let subscriber = Registry::default()
+            .with(filter)
+            .with(OpenTelemetryLayer::new(provider.tracer("test")));
+        tracing::subscriber::set_default(subscriber)
+    }
+
+    #[test]
+    fn test_debug_trigger_matches() {
+        let trigger = DebugTrigger::new()
+            .baggage("debug", "true")
+            .header(HeaderName::from_static("x-debug"));
+        let mut headers = HeaderMap::new();
+        let context = Context::new();
+
+        assert!(!trigger.matches(&headers, &context));
+        assert!(!DebugTrigger::new().matches(&headers, &context));
+
+        let baggage = context.with_baggage([KeyValue::new("debug", "false")]);
+        assert!(!trigger.matches(&headers, &baggage));
+        let baggage = context.with_baggage([KeyValue::new("debug", "true")]);
+        assert!(trigger.matches(&headers, &baggage));
+
+        headers.insert("x-debug", HeaderValue::from_static("1"));
+        assert!(trigger.matches(&headers, &context));
+    }
+
+    #[test]
+    fn test_debug_logging_enabled_for_span_scope() {
+        let _guard = set_default_subscriber(Sampler::AlwaysOn);
+        let triggered = tracing::info_span!("triggered");
+        let regular = tracing::info_span!("regular");
+
+        assert!(enable_debug_logging(&triggered));
+
+        triggered.in_scope(|| {
+            assert!(tracing::event_enabled!(Level::DEBUG));
+            assert!(!tracing::event_enabled!(Level::TRACE));
+            tracing::info_span!("child").in_scope(|| {
+                assert!(tracing::event_enabled!(Level::DEBUG));
+            });
+        });
+        regular.in_scope(|| {
+            assert!(!tracing::event_enabled!(Level::DEBUG));
+        });
+        assert!(!tracing::event_enabled!(Level::DEBUG));
+    }
+
+    #[test]
+    fn test_debug_logging_requires_sampled_trace() {
+        let _guard = set_default_subscriber(Sampler::AlwaysOff);
+        let span = tracing::info_span!("unsampled");
+
+        assert!(!enable_debug_logging(&span));
+        span.in_scope(|| {
+            assert!(!tracing::event_enabled!(Level::DEBUG));
+        });
+    }
+}
@@ -0,0 +1,191 @@
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{Registry, filter::Targets, reload};
+
+use super::TracingFilter;
+
+/// Error returned by [`FilterHandle`] when the filter cannot be accessed.
+///
+/// This happens when the subscriber the filter was installed into has been dropped,
+/// or when the lock protecting the filter has been poisoned.
+pub type ReloadError = reload::Error;
+
+/// Handle to change the [`TracingFilter`] of a running subscriber.
+///
+/// The handle is returned by [`crate::init_reloadable_tracing_with_fallbacks`] and
+/// can be cloned and shared freely. Any change applies immediately to all
+/// subsequent events and spans.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::{filter::parse_directives, init_reloadable_tracing};
+/// use tracing::Level;
+///
+/// let (tracer_provider, filter_handle) = init_reloadable_tracing!(Level::INFO);
+///
+/// filter_handle.set_log_level(Level::DEBUG)?;
+/// filter_handle.set_tracing_directives(parse_directives("hyper=warn")?)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilterHandle(reload::Handle<TracingFilter, Registry>);
+
+impl FilterHandle {
+    /// Wraps the provided filter into a reloadable layer.
+    ///
+    /// Returns the layer to be installed into a [`Registry`] and a handle to change it.
+    pub fn new(filter: TracingFilter) -> (reload::Layer<TracingFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(filter);
+        (layer, Self(handle))
+    }
+
+    /// Returns a copy of the current filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn filter(&self) -> Result<TracingFilter, ReloadError> {
+        self.0.with_current(Clone::clone)
+    }
+
+    /// Replaces the current filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn reload(&self, filter: TracingFilter) -> Result<(), ReloadError> {
+        self.0.reload(filter)
+    }
+
+    /// Changes the default level for events, keeping per-target directives.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn set_log_level(
+        &self,
+        level: impl Into<LevelFilter>,
+    ) -> Result<(), ReloadError> {
+        let level = level.into();
+        self.0.modify(|filter| {
+            filter.log_directives = filter.log_directives.clone().with_default(level);
+        })
+    }
+
+    /// Changes the default level for spans, keeping per-target directives.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn set_tracing_level(
+        &self,
+        level: impl Into<LevelFilter>,
+    ) -> Result<(), ReloadError> {
+        let level = level.into();
+        self.0.modify(|filter| {
+            filter.tracing_directives =
+                filter.tracing_directives.clone().with_default(level);
+        })
+    }
+
+    /// Replaces the directives for events.
+    ///
+    /// If `directives` has no bare level, the current default event level is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn set_log_directives(&self, directives: Targets) -> Result<(), ReloadError> {
+        self.0.modify(|filter| {
+            filter.log_directives =
+                replace_directives(&filter.log_directives, directives);
+        })
+    }
+
+    /// Replaces the directives for spans.
+    ///
+    /// If `directives` has no bare level, the current default span level is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn set_tracing_directives(&self, directives: Targets) -> Result<(), ReloadError> {
+        self.0.modify(|filter| {
+            filter.tracing_directives =
+                replace_directives(&filter.tracing_directives, directives);
+        })
+    }
+
+    /// Sets or clears the directives for events inside spans with per-request debug
+    /// logging enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ReloadError`] if the subscriber has been dropped.
+    pub fn set_debug_directives(
+        &self,
+        directives: Option<Targets>,
+    ) -> Result<(), ReloadError> {
+        self.0.modify(|filter| filter.debug_directives = directives)
+    }
+
+    /// Applies several changes to the current filter at once.
+    #[cfg(feature = "axum")]
+    pub(super) fn modify(
+        &self,
+        f: impl FnOnce(&mut TracingFilter),
+    ) -> Result<(), ReloadError> {
+        self.0.modify(f)
+    }
+}
+
+pub(super) fn replace_directives(current: &Targets, directives: Targets) -> Targets {
+    match (directives.default_level(), current.default_level()) {
+        (None, Some(level)) => directives.with_default(level),
+        _ => directives,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_directives;
+    use assert2::assert;
+    use tracing::Level;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_filter_handle_updates_filter() {
+        let filter = TracingFilter::new(Level::INFO, Level::INFO)
+            .with_log_directives(parse_directives("hyper=warn").unwrap());
+        let (layer, handle) = FilterHandle::new(filter);
+        let subscriber = Registry::default().with(layer);
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        assert!(!tracing::event_enabled!(target: "my_app", Level::DEBUG));
+
+        handle.set_log_level(Level::DEBUG).unwrap();
+        assert!(tracing::event_enabled!(target: "my_app", Level::DEBUG));
+        assert!(!tracing::event_enabled!(target: "hyper", Level::INFO));
+
+        handle
+            .set_log_directives(parse_directives("my_app=trace").unwrap())
+            .unwrap();
+        assert!(tracing::event_enabled!(target: "hyper", Level::INFO));
+        assert!(tracing::event_enabled!(target: "my_app", Level::TRACE));
+        assert!(!tracing::event_enabled!(target: "other", Level::TRACE));
+
+        let filter = handle.filter().unwrap();
+        assert!(filter.log_directives().to_string() == "my_app=trace,debug");
+    }
+
+    #[test]
+    fn test_filter_handle_fails_when_subscriber_is_dropped() {
+        let (layer, handle) =
+            FilterHandle::new(TracingFilter::new(Level::INFO, Level::INFO));
+        drop(Registry::default().with(layer));
+
+        assert!(let Err(_) = handle.set_tracing_level(Level::DEBUG));
+        assert!(let Err(_) = handle.filter());
+    }
+}
@@ -0,0 +1,451 @@
+//! Level and per-target filtering for logs and spans.
+//!
+//! [`TracingFilter`] applies separate `RUST_LOG`-style directives to events (logs)
+//! and spans, while always keeping spans created on the OpenTelemetry instrumentation
+//! target enabled. The filter can be changed at runtime through a [`FilterHandle`].
+//!
+//! [`RateLimitFilter`] limits the rate of events per callsite to protect log output
+//! from misbehaving dependencies.
+
+use std::str::FromStr;
+use tracing::{Level, Metadata, Subscriber, subscriber::Interest};
+use tracing_opentelemetry_instrumentation_sdk::TRACING_TARGET;
+use tracing_subscriber::{
+    filter::{ParseError, Targets},
+    layer::{Context, Filter, Layer},
+    registry::LookupSpan,
+};
+
+use crate::util;
+
+mod debug;
+mod handle;
+mod rate_limit;
+#[cfg(feature = "axum")]
+mod service;
+
+pub use debug::{DebugTrigger, enable_debug_logging};
+pub use handle::{FilterHandle, ReloadError};
+pub use rate_limit::{RateLimit, RateLimitFilter, RateLimitLayer};
+#[cfg(feature = "axum")]
+pub use service::FilterService;
+
+const DEFAULT_TRACING_LEVEL: Level = Level::INFO;
+
+/// Filter for logs and spans with per-target directives.
+///
+/// Events are matched against the log directives and spans against the tracing
+/// directives. Spans on the [`TRACING_TARGET`] (e.g. HTTP server spans created by the
+/// middleware) are always enabled, regardless of the configured directives.
+///
+/// Directives use the same syntax as `RUST_LOG`, e.g. `info,hyper=warn,my_app=debug`.
+/// A bare level sets the default for targets without a more specific directive.
+///
+/// Optional debug directives apply to events inside spans with per-request debug
+/// logging enabled (see [`DebugTrigger`] and [`enable_debug_logging`]), which allows
+/// lowering the log level for a single request without changing the global level.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::filter::{TracingFilter, parse_directives};
+/// use tracing::Level;
+///
+/// let filter = TracingFilter::new(Level::INFO, Level::INFO)
+///     .with_log_directives(parse_directives("hyper=warn,aws_smithy_runtime=error")?)
+///     .with_tracing_directives(parse_directives("my_app=debug")?)
+///     .with_debug_directives(parse_directives("debug,hyper=info")?);
+/// # Ok::<(), tracing_subscriber::filter::ParseError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct TracingFilter {
+    log_directives: Targets,
+    tracing_directives: Targets,
+    debug_directives: Option<Targets>,
+}
+
+impl TracingFilter {
+    /// Creates a new filter with default levels for events and spans.
+    ///
+    /// # Arguments
+    ///
+    /// - `log_level`: The minimum level for events
+    /// - `tracing_level`: The minimum level for spans
+    pub fn new(log_level: Level, tracing_level: Level) -> Self {
+        Self {
+            log_directives: Targets::new().with_default(log_level),
+            tracing_directives: Targets::new().with_default(tracing_level),
+            debug_directives: None,
+        }
+    }
+
+    /// Creates a new filter using `log_level` as the default level for events and
+    /// reading the remaining configuration from environment variables.
+    ///
+    /// - `RUST_LOG`: directives for events, applied on top of `log_level`
+    /// - `OTEL_LOG_LEVEL`: directives for spans, defaults to [`Level::INFO`]
+    ///
+    /// Invalid values are ignored.
+    pub fn from_level(log_level: Level) -> Self {
+        Self::from_env(log_level).0
+    }
+
+    /// Creates the filter like [`TracingFilter::from_level`] and returns the invalid
+    /// environment variables, to be reported once a subscriber is installed.
+    pub(crate) fn from_env(log_level: Level) -> (Self, Vec<InvalidDirectives>) {
+        let mut errors = Vec::new();
+        let mut read = |key| {
+            read_directives_from_env(key)
+                .unwrap_or_else(|err| {
+                    errors.push(err);
+                    None
+                })
+                .unwrap_or_default()
+        };
+        let log_directives = read("RUST_LOG");
+        let tracing_directives = read("OTEL_LOG_LEVEL");
+        let tracing_directives = match tracing_directives.default_level() {
+            Some(_) => tracing_directives,
+            None => tracing_directives.with_default(DEFAULT_TRACING_LEVEL),
+        };
+        let filter = Self {
+            log_directives: Targets::new().with_default(log_level),
+            tracing_directives,
+            debug_directives: None,
+        }
+        .with_log_directives(log_directives);
+        (filter, errors)
+    }
+
+    /// Applies additional directives for events.
+    ///
+    /// Target directives are added to the existing ones, replacing directives for the
+    /// same target. A bare level replaces the default event level.
+    pub fn with_log_directives(self, directives: Targets) -> Self {
+        Self {
+            log_directives: merge_directives(self.log_directives, directives),
+            ..self
+        }
+    }
+
+    /// Applies additional directives for spans.
+    ///
+    /// Target directives are added to the existing ones, replacing directives for the
+    /// same target. A bare level replaces the default span level.
+    pub fn with_tracing_directives(self, directives: Targets) -> Self {
+        Self {
+            tracing_directives: merge_directives(self.tracing_directives, directives),
+            ..self
+        }
+    }
+
+    /// Sets the directives for events inside spans with per-request debug logging
+    /// enabled.
+    ///
+    /// Events are enabled if either the log directives or the debug directives enable
+    /// them. Per-request debug logging is disabled unless debug directives are set.
+    pub fn with_debug_directives(self, directives: Targets) -> Self {
+        Self {
+            debug_directives: Some(directives),
+            ..self
+        }
+    }
+
+    /// Returns the directives applied to events.
+    pub fn log_directives(&self) -> &Targets {
+        &self.log_directives
+    }
+
+    /// Returns the directives applied to spans.
+    pub fn tracing_directives(&self) -> &Targets {
+        &self.tracing_directives
+    }
+
+    /// Returns the directives applied to events inside spans with per-request debug
+    /// logging enabled.
+    pub fn debug_directives(&self) -> Option<&Targets> {
+        self.debug_directives.as_ref()
+    }
+
+    #[inline(always)]
+    fn _enabled(&self, meta: &Metadata<'_>) -> bool {
+        if meta.is_event() {
+            self.log_directives
+                .would_enable(meta.target(), meta.level())
+        } else {
+            meta.target() == TRACING_TARGET
+                || self
+                    .tracing_directives
+                    .would_enable(meta.target(), meta.level())
+        }
+    }
+
+    #[inline(always)]
+    fn _debug_enabled(&self, meta: &Metadata<'_>) -> bool {
+        meta.is_event()
+            && self.debug_directives.as_ref().is_some_and(|directives| {
+                directives.would_enable(meta.target(), meta.level())
+            })
+    }
+
+    #[inline(always)]
+    fn _enabled_with_context<S>(&self, meta: &Metadata<'_>, ctx: &Context<'_, S>) -> bool
+    where
+        S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+    {
+        self._enabled(meta)
+            || (self._debug_enabled(meta)
+                && debug::is_debug_logging_enabled(ctx.lookup_current()))
+    }
+
+    #[inline(always)]
+    fn _callsite_enabled(&self, meta: &Metadata<'_>) -> Interest {
+        if self._enabled(meta) {
+            Interest::always()
+        } else if self._debug_enabled(meta) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    }
+}
+
+impl<S> Filter<S> for TracingFilter
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, ctx: &Context<'_, S>) -> bool {
+        self._enabled_with_context(meta, ctx)
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        self._callsite_enabled(meta)
+    }
+}
+
+impl<S> Layer<S> for TracingFilter
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self._enabled_with_context(meta, &ctx)
+    }
+
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        self._callsite_enabled(meta)
+    }
+}
+
+impl From<Level> for TracingFilter {
+    #[inline]
+    fn from(level: Level) -> Self {
+        Self::from_level(level)
+    }
+}
+
+fn merge_directives(base: Targets, overrides: Targets) -> Targets {
+    let default_level = overrides.default_level();
+    let merged = base.with_targets(overrides);
+    match default_level {
+        Some(level) => merged.with_default(level),
+        None => merged,
+    }
+}
+
+/// Parses a comma-separated list of `RUST_LOG`-style directives.
+///
+/// Each directive is either a bare level (`debug`), a bare target (`my_app`, enables
+/// all levels) or a `target=level` pair (`hyper=warn`). Whitespace around directives
+/// and empty entries are ignored.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] if any of the directives is invalid.
+///
+/// # Examples
+///
+/// ```rust
+/// use telemetry_rust::filter::parse_directives;
+/// use tracing::Level;
+///
+/// let directives = parse_directives("info, hyper=warn")?;
+/// assert!(directives.would_enable("my_app", &Level::INFO));
+/// assert!(!directives.would_enable("hyper::client", &Level::INFO));
+/// # Ok::<(), tracing_subscriber::filter::ParseError>(())
+/// ```
+pub fn parse_directives(directives: &str) -> Result<Targets, ParseError> {
+    let directives = directives
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+    if directives.is_empty() {
+        Ok(Targets::new())
+    } else {
+        Targets::from_str(&directives.join(","))
+    }
+}
+
+/// Invalid directives read from an environment variable.
+#[derive(Debug)]
+pub(crate) struct InvalidDirectives {
+    pub(crate) key: &'static str,
+    pub(crate) value: String,
+    pub(crate) error: ParseError,
+}
+
+fn read_directives_from_env(
+    key: &'static str,
+) -> Result<Option<Targets>, InvalidDirectives> {
+    let Some(value) = util::env_var(key) else {
+        return Ok(None);
+    };
+    match parse_directives(&value) {
+        Ok(directives) => Ok(Some(directives)),
+        Err(error) => Err(InvalidDirectives { key, value, error }),
+    }
+}
+
+/// Reads the tracing level configuration from environment variables.
+///
+/// This function checks the `OTEL_LOG_LEVEL` environment variable to determine
+/// the minimum tracing level. If the variable is not set or contains an invalid
+/// value, it defaults to [`Level::INFO`].
+///
+/// Per-target directives in `OTEL_LOG_LEVEL` are ignored by this function, but are
+/// applied to spans by [`TracingFilter::from_level`].
+///
+/// # Supported Values
+///
+/// The environment variable should contain one of:
+/// - `ERROR` or `error`
+/// - `WARN` or `warn`
+/// - `INFO` or `info`
+/// - `DEBUG` or `debug`
+/// - `TRACE` or `trace`
+///
+/// # Returns
+///
+/// The configured [`Level`] for tracing, or [`Level::INFO`] as default
+///
+/// # Examples
+///
+/// ```bash
+/// export OTEL_LOG_LEVEL=DEBUG
+/// ```
+///
+/// ```rust
+/// use telemetry_rust::otlp::read_otel_log_level_from_env;
+///
+/// let level = read_otel_log_level_from_env();
+/// ```
+pub fn read_tracing_level_from_env() -> Level {
+    if let Some(level_str) = util::env_var("OTEL_LOG_LEVEL") {
+        level_str.parse().unwrap_or(DEFAULT_TRACING_LEVEL)
+    } else {
+        DEFAULT_TRACING_LEVEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+    use tracing::{Event, level_filters::LevelFilter};
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    #[rstest]
+    #[case("", None, vec![])]
+    #[case("debug", Some(LevelFilter::DEBUG), vec![])]
+    #[case("info, hyper=warn", Some(LevelFilter::INFO), vec![("hyper", LevelFilter::WARN)])]
+    #[case("hyper=warn,,my_app=DEBUG", None, vec![("hyper", LevelFilter::WARN), ("my_app", LevelFilter::DEBUG)])]
+    fn test_parse_directives(
+        #[case] input: &str,
+        #[case] expected_default: Option<LevelFilter>,
+        #[case] expected_targets: Vec<(&str, LevelFilter)>,
+    ) {
+        let directives = parse_directives(input).unwrap();
+        let mut targets = directives.iter().collect::<Vec<_>>();
+        targets.sort();
+
+        assert!(directives.default_level() == expected_default);
+        assert!(targets == expected_targets);
+    }
+
+    #[test]
+    fn test_parse_directives_error() {
+        assert!(let Err(_) = parse_directives("hyper=loud"));
+    }
+
+    #[test]
+    fn test_merge_directives() {
+        let filter = TracingFilter::new(Level::INFO, Level::WARN)
+            .with_log_directives(parse_directives("hyper=warn,my_app=debug").unwrap())
+            .with_log_directives(parse_directives("error,hyper=trace").unwrap())
+            .with_tracing_directives(parse_directives("my_app=debug").unwrap());
+
+        let log = filter.log_directives();
+        assert!(log.default_level() == Some(LevelFilter::ERROR));
+        assert!(log.would_enable("hyper::client", &Level::TRACE));
+        assert!(log.would_enable("my_app", &Level::DEBUG));
+        assert!(!log.would_enable("other", &Level::WARN));
+
+        let tracing = filter.tracing_directives();
+        assert!(tracing.default_level() == Some(LevelFilter::WARN));
+        assert!(tracing.would_enable("my_app", &Level::DEBUG));
+        assert!(!tracing.would_enable("other", &Level::INFO));
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        events: std::sync::Mutex<Vec<String>>,
+        spans: std::sync::Mutex<Vec<String>>,
+    }
+
+    struct CountingLayer(std::sync::Arc<Counter>);
+
+    impl<S: Subscriber> Layer<S> for CountingLayer {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            let meta = event.metadata();
+            let name = format!("{}:{}", meta.target(), meta.level());
+            self.0.events.lock().unwrap().push(name);
+        }
+
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _: &tracing::span::Id,
+            _: Context<'_, S>,
+        ) {
+            let name = attrs.metadata().name().to_owned();
+            self.0.spans.lock().unwrap().push(name);
+        }
+    }
+
+    #[test]
+    fn test_filter_applies_directives_separately() {
+        let counter = std::sync::Arc::new(Counter::default());
+        let filter = TracingFilter::new(Level::INFO, Level::WARN)
+            .with_log_directives(parse_directives("hyper=warn,my_app=debug").unwrap())
+            .with_tracing_directives(parse_directives("my_app=trace").unwrap());
+        let subscriber =
+            Registry::default().with(CountingLayer(counter.clone()).with_filter(filter));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "hyper::proto", "skipped");
+            tracing::warn!(target: "hyper::proto", "kept");
+            tracing::debug!(target: "my_app", "kept");
+            tracing::debug!(target: "other", "skipped");
+
+            let _ = tracing::info_span!(target: "other", "skipped_span");
+            let _ = tracing::trace_span!(target: "my_app", "app_span");
+            let _ = tracing::trace_span!(target: TRACING_TARGET, "otel_span");
+        });
+
+        let events = counter.events.lock().unwrap().clone();
+        let spans = counter.spans.lock().unwrap().clone();
+        assert!(events == ["hyper::proto:WARN", "my_app:DEBUG"]);
+        assert!(spans == ["app_span", "otel_span"]);
+    }
+}
@@ -0,0 +1,750 @@
+use std::{
+    any::TypeId,
+    collections::{HashMap, hash_map::DefaultHasher},
+    fmt::{self, Write as _},
+    hash::{Hash, Hasher},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use tracing::{
+    Dispatch, Event, Level, Metadata, Subscriber,
+    callsite::{DefaultCallsite, Identifier},
+    field::{Field, FieldSet, Value, Visit},
+    metadata::Kind,
+    span,
+    subscriber::Interest,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Target of the summary events of suppressed events.
+const SUMMARY_TARGET: &str = "otel::rate_limit";
+const SUMMARY_FIELDS: &[&str] = &[
+    "message",
+    "suppressed_count",
+    "suppressed_reason",
+    "suppressed_target",
+    "suppressed_event",
+];
+/// Maximum delay between the first event suppressed by a token bucket and its summary.
+const MAX_SUMMARY_DELAY: Duration = Duration::from_secs(1);
+
+/// Rate limit configuration for events of a single level.
+///
+/// Combines an optional token bucket, limiting the number of events per callsite,
+/// with optional deduplication of identical messages.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telemetry_rust::filter::RateLimit;
+///
+/// // 10 events per second per callsite, with bursts of up to 50 events,
+/// // and identical messages collapsed for 5 seconds
+/// let limit = RateLimit::new()
+///     .per_second(10)
+///     .burst(50)
+///     .deduplicate(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RateLimit {
+    per_second: Option<f64>,
+    burst: Option<f64>,
+    deduplicate: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Creates a limit that lets all events through until configured.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits events per callsite to `events` per second.
+    ///
+    /// Unless [`RateLimit::burst`] is set, the bucket holds up to `events` tokens.
+    pub fn per_second(self, events: u32) -> Self {
+        Self {
+            per_second: Some(f64::from(events)),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of events per callsite emitted in a burst.
+    pub fn burst(self, events: u32) -> Self {
+        Self {
+            burst: Some(f64::from(events)),
+            ..self
+        }
+    }
+
+    /// Suppresses events identical to the last emitted event of the same callsite
+    /// during the given window.
+    ///
+    /// Events are identical if all their fields, including `message`, are equal.
+    pub fn deduplicate(self, window: Duration) -> Self {
+        Self {
+            deduplicate: Some(window),
+            ..self
+        }
+    }
+
+    fn capacity(&self) -> Option<f64> {
+        self.burst.or(self.per_second)
+    }
+}
+
+#[derive(Debug)]
+struct CallsiteState {
+    meta: &'static Metadata<'static>,
+    tokens: f64,
+    refilled_at: Instant,
+    last_message: Option<LastMessage>,
+    /// Repeats of the last message suppressed by deduplication.
+    repeated: u64,
+    /// Events suppressed by the token bucket, with the time the first of them was.
+    rate_limited: Option<(u64, Instant)>,
+}
+
+#[derive(Debug)]
+struct LastMessage {
+    hash: u64,
+    message: String,
+    emitted_at: Instant,
+}
+
+impl CallsiteState {
+    fn repeated_deadline(&self, limit: &RateLimit) -> Option<Instant> {
+        let last = self.last_message.as_ref().filter(|_| self.repeated > 0)?;
+        Some(last.emitted_at + limit.deduplicate?)
+    }
+
+    fn rate_limited_deadline(&self, limit: &RateLimit) -> Option<Instant> {
+        let (_, since) = self.rate_limited?;
+        let refill = Duration::try_from_secs_f64(1.0 / limit.per_second?)
+            .map_or(MAX_SUMMARY_DELAY, |refill| refill.min(MAX_SUMMARY_DELAY));
+        Some(since + refill)
+    }
+
+    fn next_deadline(&self, limit: &RateLimit) -> Option<Instant> {
+        match (
+            self.repeated_deadline(limit),
+            self.rate_limited_deadline(limit),
+        ) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn take_repeated(&mut self, deadline: Instant, summaries: &mut Vec<Summary>) {
+        if let Some(last) = &self.last_message
+            && self.repeated > 0
+        {
+            summaries.push(Summary {
+                meta: self.meta,
+                deadline,
+                reason: "repeated",
+                message: last.message.clone(),
+                count: std::mem::take(&mut self.repeated),
+            });
+        }
+    }
+
+    fn take_rate_limited(&mut self, deadline: Instant, summaries: &mut Vec<Summary>) {
+        if let Some((count, _)) = self.rate_limited.take() {
+            summaries.push(Summary {
+                meta: self.meta,
+                deadline,
+                reason: "rate_limited",
+                message: "events suppressed by rate limit".to_owned(),
+                count,
+            });
+        }
+    }
+
+    /// Moves the counts due before `now` to `summaries`.
+    fn take_expired(
+        &mut self,
+        limit: &RateLimit,
+        now: Instant,
+        summaries: &mut Vec<Summary>,
+    ) {
+        if let Some(deadline) = self.repeated_deadline(limit)
+            && deadline <= now
+        {
+            self.take_repeated(deadline, summaries);
+        }
+        if let Some(deadline) = self.rate_limited_deadline(limit)
+            && deadline <= now
+        {
+            self.take_rate_limited(deadline, summaries);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    callsites: HashMap<Identifier, CallsiteState>,
+    /// Earliest deadline of the pending summaries, if any.
+    next_summary: Option<Instant>,
+}
+
+/// Events of a callsite suppressed by a [`RateLimitFilter`], reported by a summary event.
+#[derive(Debug)]
+struct Summary {
+    meta: &'static Metadata<'static>,
+    deadline: Instant,
+    reason: &'static str,
+    message: String,
+    count: u64,
+}
+
+impl Summary {
+    fn emit<S: Subscriber, L: Layer<S>>(&self, layer: &L, ctx: Context<'_, S>) {
+        let meta = summary_metadata(self.meta.level());
+        let fields = meta.fields();
+        let field = |name| fields.field(name).expect("summary fields are static");
+        let (message, count, reason, target, event) = (
+            field("message"),
+            field("suppressed_count"),
+            field("suppressed_reason"),
+            field("suppressed_target"),
+            field("suppressed_event"),
+        );
+        let values: [(&Field, Option<&dyn Value>); 5] = [
+            (&message, Some(&self.message.as_str())),
+            (&count, Some(&self.count)),
+            (&reason, Some(&self.reason)),
+            (&target, Some(&self.meta.target())),
+            (&event, Some(&self.meta.name())),
+        ];
+        let values = fields.value_set(&values);
+        layer.on_event(&Event::new_child_of(None, meta, &values), ctx);
+    }
+}
+
+macro_rules! summary_metadata {
+    ($($level:ident),*) => {
+        /// Metadata of the summary events of the given level.
+        fn summary_metadata(level: &Level) -> &'static Metadata<'static> {
+            $(
+                if *level == Level::$level {
+                    static CALLSITE: DefaultCallsite = DefaultCallsite::new(&META);
+                    static META: Metadata<'static> = Metadata::new(
+                        "suppressed events",
+                        SUMMARY_TARGET,
+                        Level::$level,
+                        Some(file!()),
+                        Some(line!()),
+                        Some(module_path!()),
+                        FieldSet::new(SUMMARY_FIELDS, Identifier(&CALLSITE)),
+                        Kind::EVENT,
+                    );
+                    return &META;
+                }
+            )*
+            unreachable!("all levels are covered")
+        }
+    };
+}
+
+summary_metadata!(ERROR, WARN, INFO, DEBUG, TRACE);
+
+/// Rate limits of events per callsite, applied to a layer with [`RateLimitFilter::layer`].
+///
+/// Events suppressed by the limits are counted per callsite, and reported to the
+/// wrapped layer by a summary event on the `otel::rate_limit` target, with the level of
+/// the callsite and the fields:
+/// - `message`: The repeated message, or `events suppressed by rate limit`
+/// - `suppressed_count`: Number of suppressed events
+/// - `suppressed_reason`: `repeated` for deduplicated messages, `rate_limited` for
+///   events suppressed by the token bucket
+/// - `suppressed_target`, `suppressed_event`: Target and name of the callsite
+///
+/// Repeats of a message are summarized when the deduplication window expires, or right
+/// before another message of the callsite is emitted. Events suppressed by the token
+/// bucket are summarized once the bucket holds a token again, and at least every second.
+/// Summaries are emitted with the next event reaching the layer after they are due, so
+/// they are only delayed while no event is logged at all.
+///
+/// Spans are never limited, and levels without a configured [`RateLimit`] are not
+/// limited either. Each filter keeps its own counts.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use telemetry_rust::{
+///     filter::{RateLimit, RateLimitFilter},
+///     fmt::JsonFormat,
+/// };
+/// use tracing::Level;
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let filter = RateLimitFilter::new()
+///     .with_limit(Level::WARN, RateLimit::new().per_second(10))
+///     .with_limit(
+///         Level::ERROR,
+///         RateLimit::new().deduplicate(Duration::from_secs(10)),
+///     );
+///
+/// let fmt_layer = tracing_subscriber::fmt::layer().json().event_format(JsonFormat);
+/// let subscriber = tracing_subscriber::registry().with(filter.layer(fmt_layer));
+/// ```
+#[derive(Debug, Default)]
+pub struct RateLimitFilter {
+    limits: [Option<RateLimit>; 5],
+    state: Mutex<State>,
+    has_pending: AtomicBool,
+}
+
+impl RateLimitFilter {
+    /// Creates a filter without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the limit for events of the given level.
+    pub fn with_limit(mut self, level: Level, limit: RateLimit) -> Self {
+        self.limits[level_index(&level)] = Some(limit);
+        self
+    }
+
+    /// Sets the same limit for events of all levels.
+    pub fn with_limit_for_all_levels(self, limit: RateLimit) -> Self {
+        Self {
+            limits: [Some(limit); 5],
+            ..self
+        }
+    }
+
+    /// Applies the limits to the events of the given layer, e.g. the fmt layer.
+    ///
+    /// The layer should not have a per-layer filter, as it also receives the summary
+    /// events: filter the returned [`RateLimitLayer`] instead.
+    pub fn layer<L>(self, inner: L) -> RateLimitLayer<L> {
+        RateLimitLayer {
+            inner,
+            filter: self,
+        }
+    }
+
+    fn limit(&self, level: &Level) -> Option<&RateLimit> {
+        self.limits[level_index(level)].as_ref()
+    }
+
+    /// Returns `true` if the event should be emitted, after adding the summaries due
+    /// before it to `summaries`.
+    fn check(
+        &self,
+        event: &Event<'_>,
+        now: Instant,
+        summaries: &mut Vec<Summary>,
+    ) -> bool {
+        let meta = event.metadata();
+        let limit = self.limit(meta.level());
+        if limit.is_none() && !self.has_pending.load(Ordering::Relaxed) {
+            return true;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return true;
+        };
+
+        if state.next_summary.is_some_and(|deadline| deadline <= now) {
+            let mut next_summary = None;
+            for callsite in state.callsites.values_mut() {
+                let Some(limit) = self.limit(callsite.meta.level()) else {
+                    continue;
+                };
+                callsite.take_expired(limit, now, summaries);
+                next_summary = earliest(next_summary, callsite.next_deadline(limit));
+            }
+            state.next_summary = next_summary;
+            summaries.sort_by_key(|summary| summary.deadline);
+        }
+
+        let enabled = match limit {
+            Some(limit) => check_callsite(&mut state, event, limit, now, summaries),
+            None => true,
+        };
+        self.has_pending
+            .store(state.next_summary.is_some(), Ordering::Relaxed);
+        enabled
+    }
+}
+
+fn check_callsite(
+    state: &mut State,
+    event: &Event<'_>,
+    limit: &RateLimit,
+    now: Instant,
+    summaries: &mut Vec<Summary>,
+) -> bool {
+    let meta = event.metadata();
+    let message_hash = limit.deduplicate.map(|_| hash_fields(event));
+    let callsite =
+        state
+            .callsites
+            .entry(meta.callsite())
+            .or_insert_with(|| CallsiteState {
+                meta,
+                tokens: limit.capacity().unwrap_or_default(),
+                refilled_at: now,
+                last_message: None,
+                repeated: 0,
+                rate_limited: None,
+            });
+
+    if let (Some(window), Some(hash), Some(last)) =
+        (limit.deduplicate, message_hash, &callsite.last_message)
+        && hash == last.hash
+        && now.duration_since(last.emitted_at) < window
+    {
+        callsite.repeated += 1;
+        state.next_summary = earliest(state.next_summary, callsite.next_deadline(limit));
+        return false;
+    }
+
+    if let (Some(per_second), Some(capacity)) = (limit.per_second, limit.capacity()) {
+        let elapsed = now.duration_since(callsite.refilled_at).as_secs_f64();
+        callsite.tokens = (callsite.tokens + elapsed * per_second).min(capacity);
+        callsite.refilled_at = now;
+        if callsite.tokens < 1.0 {
+            callsite.rate_limited.get_or_insert((0, now)).0 += 1;
+            state.next_summary =
+                earliest(state.next_summary, callsite.next_deadline(limit));
+            return false;
+        }
+        callsite.tokens -= 1.0;
+    }
+
+    if let Some(hash) = message_hash {
+        // the repeats of the previous message end with another message
+        callsite.take_repeated(now, summaries);
+        callsite.last_message = Some(LastMessage {
+            hash,
+            message: event_message(event).unwrap_or_else(|| meta.name().to_owned()),
+            emitted_at: now,
+        });
+    }
+    true
+}
+
+fn earliest(a: Option<Instant>, b: Option<Instant>) -> Option<Instant> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Layer applying the limits of a [`RateLimitFilter`] to the events of another layer.
+///
+/// Created with [`RateLimitFilter::layer`].
+#[derive(Debug)]
+pub struct RateLimitLayer<L> {
+    inner: L,
+    filter: RateLimitFilter,
+}
+
+impl<S, L> Layer<S> for RateLimitLayer<L>
+where
+    S: Subscriber,
+    L: Layer<S>,
+{
+    fn on_register_dispatch(&self, subscriber: &Dispatch) {
+        self.inner.on_register_dispatch(subscriber);
+    }
+
+    fn on_layer(&mut self, subscriber: &mut S) {
+        self.inner.on_layer(subscriber);
+    }
+
+    fn register_callsite(&self, meta: &'static Metadata<'static>) -> Interest {
+        self.inner.register_callsite(meta)
+    }
+
+    fn enabled(&self, meta: &Metadata<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.enabled(meta, ctx)
+    }
+
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, S>,
+    ) {
+        self.inner.on_new_span(attrs, id, ctx);
+    }
+
+    fn on_record(&self, span: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        self.inner.on_record(span, values, ctx);
+    }
+
+    fn on_follows_from(&self, span: &span::Id, follows: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_follows_from(span, follows, ctx);
+    }
+
+    fn event_enabled(&self, event: &Event<'_>, ctx: Context<'_, S>) -> bool {
+        self.inner.event_enabled(event, ctx)
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut summaries = Vec::new();
+        let enabled = self.filter.check(event, Instant::now(), &mut summaries);
+        for summary in &summaries {
+            summary.emit(&self.inner, ctx.clone());
+        }
+        if enabled {
+            self.inner.on_event(event, ctx);
+        }
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_enter(id, ctx);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_exit(id, ctx);
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        self.inner.on_close(id, ctx);
+    }
+
+    fn on_id_change(&self, old: &span::Id, new: &span::Id, ctx: Context<'_, S>) {
+        self.inner.on_id_change(old, new, ctx);
+    }
+
+    unsafe fn downcast_raw(&self, id: TypeId) -> Option<*const ()> {
+        if id == TypeId::of::<Self>() {
+            Some(self as *const Self as *const ())
+        } else {
+            // SAFETY: forwarded to the wrapped layer, which upholds the contract
+            unsafe { self.inner.downcast_raw(id) }
+        }
+    }
+}
+
+fn level_index(level: &Level) -> usize {
+    match *level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+fn hash_fields(event: &Event<'_>) -> u64 {
+    let mut visitor = HashVisitor(DefaultHasher::new());
+    event.record(&mut visitor);
+    visitor.0.finish()
+}
+
+struct HashVisitor(DefaultHasher);
+
+impl Visit for HashVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        field.name().hash(&mut self.0);
+        let _ = write!(self, "{value:?}");
+    }
+}
+
+impl fmt::Write for HashVisitor {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+fn event_message(event: &Event<'_>) -> Option<String> {
+    let mut visitor = MessageVisitor(None);
+    event.record(&mut visitor);
+    visitor.0
+}
+
+struct MessageVisitor(Option<String>);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0 = Some(value.to_owned());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use std::sync::Arc;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    type Messages = Vec<(String, Option<u64>)>;
+
+    #[derive(Clone, Default)]
+    struct Collected(Arc<Mutex<Messages>>);
+
+    #[derive(Default)]
+    struct CollectVisitor(String, Option<u64>);
+
+    impl Visit for CollectVisitor {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "message" {
+                self.0 = value.to_owned();
+            }
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if field.name() == "suppressed_count" {
+                self.1 = Some(value);
+            }
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.0, "{value:?}");
+            }
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Collected {
+        fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+            let mut visitor = CollectVisitor::default();
+            event.record(&mut visitor);
+            self.0.lock().unwrap().push((visitor.0, visitor.1));
+        }
+    }
+
+    fn collect(filter: RateLimitFilter, emit: impl Fn()) -> Messages {
+        let collected = Collected::default();
+        let subscriber = Registry::default().with(filter.layer(collected.clone()));
+        tracing::subscriber::with_default(subscriber, emit);
+        collected.0.lock().unwrap().clone()
+    }
+
+    fn message(message: &str, suppressed: Option<u64>) -> (String, Option<u64>) {
+        (message.to_owned(), suppressed)
+    }
+
+    #[test]
+    fn test_rate_limit_per_callsite() {
+        let filter = RateLimitFilter::new()
+            .with_limit(Level::WARN, RateLimit::new().per_second(1).burst(2));
+
+        let events = collect(filter, || {
+            for i in 0..5 {
+                tracing::warn!("limited {i}");
+                tracing::info!("unlimited {i}");
+            }
+            tracing::warn!("other callsite");
+        });
+
+        let limited = events
+            .iter()
+            .filter(|(message, _)| !message.starts_with("unlimited"))
+            .collect::<Vec<_>>();
+        assert!(events.len() == 8);
+        assert!(
+            limited
+                == [
+                    &message("limited 0", None),
+                    &message("limited 1", None),
+                    &message("other callsite", None),
+                ]
+        );
+    }
+
+    #[test]
+    fn test_deduplicate_identical_messages() {
+        let filter = RateLimitFilter::new().with_limit_for_all_levels(
+            RateLimit::new().deduplicate(Duration::from_secs(60)),
+        );
+
+        let events = collect(filter, || {
+            for message in ["a", "a", "a", "b", "b", "a", "c", "b"] {
+                tracing::error!("{message}");
+            }
+        });
+
+        assert!(
+            events
+                == [
+                    message("a", None),
+                    message("a", Some(2)),
+                    message("b", None),
+                    message("b", Some(1)),
+                    message("a", None),
+                    message("c", None),
+                    message("b", None),
+                ]
+        );
+    }
+
+    #[test]
+    fn test_summary_after_burst() {
+        let filter = RateLimitFilter::new()
+            .with_limit(
+                Level::ERROR,
+                RateLimit::new().deduplicate(Duration::from_millis(10)),
+            )
+            .with_limit(Level::WARN, RateLimit::new().per_second(100).burst(1));
+
+        let events = collect(filter, || {
+            for _ in 0..3 {
+                tracing::error!("repeated");
+            }
+            for _ in 0..3 {
+                tracing::warn!("limited");
+            }
+            std::thread::sleep(Duration::from_millis(20));
+            tracing::info!("unrelated");
+        });
+
+        assert!(
+            events
+                == [
+                    message("repeated", None),
+                    message("limited", None),
+                    message("repeated", Some(2)),
+                    message("events suppressed by rate limit", Some(2)),
+                    message("unrelated", None),
+                ]
+        );
+    }
+
+    #[test]
+    fn test_filters_keep_own_counts() {
+        let filter = || {
+            RateLimitFilter::new().with_limit_for_all_levels(
+                RateLimit::new().deduplicate(Duration::from_secs(60)),
+            )
+        };
+        let (first, second) = (Collected::default(), Collected::default());
+        let subscriber = Registry::default()
+            .with(filter().layer(first.clone()))
+            .with(filter().layer(second.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for message in ["a", "a", "a", "b"] {
+                tracing::error!("{message}");
+            }
+        });
+
+        let expected = [
+            message("a", None),
+            message("a", Some(2)),
+            message("b", None),
+        ];
+        assert!(*first.0.lock().unwrap() == expected);
+        assert!(*second.0.lock().unwrap() == expected);
+    }
+}
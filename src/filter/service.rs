@@ -0,0 +1,312 @@
+use bytes::Bytes;
+use futures_util::future::BoxFuture;
+use http::{Method, Request, Response, StatusCode, header};
+use http_body::Body;
+use http_body_util::{BodyExt, Full, Limited};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    task::{Context, Poll},
+};
+use tower::Service;
+
+use super::{FilterHandle, ReloadError, handle::replace_directives, parse_directives};
+
+const MAX_BODY_SIZE: usize = 16 * 1024;
+
+/// HTTP service to read and change the current [`TracingFilter`](super::TracingFilter)
+/// at runtime.
+///
+/// - `GET` returns the current directives as JSON:
+///   `{"log": "...", "tracing": "...", "debug": "..."}`, where `debug` is omitted if
+///   per-request debug logging is disabled
+/// - `PUT` accepts the same JSON object, all fields are optional, and replaces the
+///   corresponding directives (see [`FilterHandle::set_log_directives`],
+///   [`FilterHandle::set_tracing_directives`] and [`FilterHandle::set_debug_directives`]).
+///   `"debug": null` disables per-request debug logging. All changes are applied at
+///   once, or none if any of the directives is invalid. Responds with the updated
+///   directives.
+///
+/// The service is framework-agnostic, and provided instead of a ready-made axum
+/// `Router` so that the `axum` feature does not depend on the axum crate: mount it on
+/// any route, e.g. with `Router::route_service`. Anyone reaching the route can change
+/// the log output, so prefer an admin router served on an internal port.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::Router;
+/// use telemetry_rust::init_reloadable_tracing;
+/// use tracing::Level;
+///
+/// let (tracer_provider, filter_handle) = init_reloadable_tracing!(Level::INFO);
+///
+/// let admin: Router = Router::new().route_service("/log-filter", filter_handle.service());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FilterService {
+    handle: FilterHandle,
+}
+
+impl FilterHandle {
+    /// Creates an HTTP service to read and change the filter at runtime.
+    ///
+    /// See [`FilterService`] for the supported requests.
+    pub fn service(&self) -> FilterService {
+        FilterService {
+            handle: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FilterDirectives {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    log: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tracing: Option<String>,
+    /// `None` if omitted, `Some(None)` if `null`.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    debug: Option<Option<String>>,
+}
+
+fn deserialize_nullable<'de, D>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorMessage {
+    error: String,
+}
+
+impl FilterService {
+    fn current(&self) -> Response<Full<Bytes>> {
+        match self.handle.filter() {
+            Ok(filter) => {
+                let directives = FilterDirectives {
+                    log: Some(filter.log_directives().to_string()),
+                    tracing: Some(filter.tracing_directives().to_string()),
+                    debug: filter
+                        .debug_directives()
+                        .map(|directives| Some(directives.to_string())),
+                };
+                json_response(StatusCode::OK, &directives)
+            }
+            Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err),
+        }
+    }
+
+    fn update(&self, body: &[u8]) -> Response<Full<Bytes>> {
+        match self.try_update(body) {
+            Ok(()) => self.current(),
+            Err((status, error)) => error_response(status, error),
+        }
+    }
+
+    fn try_update(&self, body: &[u8]) -> Result<(), (StatusCode, String)> {
+        let bad_request = |err: &dyn ToString| (StatusCode::BAD_REQUEST, err.to_string());
+        let parse = |directives: Option<&str>| {
+            directives
+                .map(parse_directives)
+                .transpose()
+                .map_err(|err| bad_request(&err))
+        };
+
+        let directives = serde_json::from_slice::<FilterDirectives>(body)
+            .map_err(|err| bad_request(&err))?;
+        let log_directives = parse(directives.log.as_deref())?;
+        let tracing_directives = parse(directives.tracing.as_deref())?;
+        let debug_directives = directives
+            .debug
+            .as_ref()
+            .map(|debug| parse(debug.as_deref()))
+            .transpose()?;
+
+        self.handle
+            .modify(|filter| {
+                if let Some(log_directives) = log_directives {
+                    filter.log_directives =
+                        replace_directives(&filter.log_directives, log_directives);
+                }
+                if let Some(tracing_directives) = tracing_directives {
+                    filter.tracing_directives = replace_directives(
+                        &filter.tracing_directives,
+                        tracing_directives,
+                    );
+                }
+                if let Some(debug_directives) = debug_directives {
+                    filter.debug_directives = debug_directives;
+                }
+            })
+            .map_err(|err: ReloadError| {
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+            })?;
+        tracing::info!(
+            log = ?directives.log,
+            tracing = ?directives.tracing,
+            debug = ?directives.debug,
+            "tracing filter updated",
+        );
+
+        Ok(())
+    }
+}
+
+impl<B> Service<Request<B>> for FilterService
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    type Response = Response<Full<Bytes>>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move {
+            let response = match *req.method() {
+                Method::GET => this.current(),
+                Method::PUT => {
+                    let body = Limited::new(req.into_body(), MAX_BODY_SIZE);
+                    match body.collect().await {
+                        Ok(body) => this.update(&body.to_bytes()),
+                        Err(err) => error_response(StatusCode::BAD_REQUEST, err),
+                    }
+                }
+                _ => {
+                    let mut response = error_response(
+                        StatusCode::METHOD_NOT_ALLOWED,
+                        "method not allowed",
+                    );
+                    response.headers_mut().insert(
+                        header::ALLOW,
+                        header::HeaderValue::from_static("GET, PUT"),
+                    );
+                    response
+                }
+            };
+            Ok(response)
+        })
+    }
+}
+
+fn json_response(status: StatusCode, value: &impl Serialize) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
+
+fn error_response(status: StatusCode, error: impl ToString) -> Response<Full<Bytes>> {
+    let error = error.to_string();
+    json_response(status, &ErrorMessage { error })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::TracingFilter;
+    use assert2::assert;
+    use tower::ServiceExt;
+    use tracing::Level;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    async fn send(
+        service: &FilterService,
+        method: Method,
+        body: &'static str,
+    ) -> (StatusCode, serde_json::Value) {
+        let req = Request::builder()
+            .method(method)
+            .body(Full::new(Bytes::from_static(body.as_bytes())))
+            .unwrap();
+        let response = service.clone().oneshot(req).await.unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_filter_service() {
+        let filter = TracingFilter::new(Level::INFO, Level::WARN);
+        let (layer, handle) = FilterHandle::new(filter);
+        let _subscriber = Registry::default().with(layer);
+        let service = handle.service();
+
+        let (status, body) = send(&service, Method::GET, "").await;
+        assert!(status == StatusCode::OK);
+        assert!(body == serde_json::json!({ "log": "info", "tracing": "warn" }));
+
+        let (status, body) =
+            send(&service, Method::PUT, r#"{"log": "hyper=warn"}"#).await;
+        assert!(status == StatusCode::OK);
+        assert!(
+            body == serde_json::json!({ "log": "hyper=warn,info", "tracing": "warn" })
+        );
+
+        let (status, _) =
+            send(&service, Method::PUT, r#"{"tracing": "hyper=loud"}"#).await;
+        assert!(status == StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&service, Method::DELETE, "").await;
+        assert!(status == StatusCode::METHOD_NOT_ALLOWED);
+
+        let (_, body) = send(&service, Method::GET, "").await;
+        assert!(
+            body == serde_json::json!({ "log": "hyper=warn,info", "tracing": "warn" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filter_service_debug_directives() {
+        let filter = TracingFilter::new(Level::INFO, Level::WARN);
+        let (layer, handle) = FilterHandle::new(filter);
+        let _subscriber = Registry::default().with(layer);
+        let service = handle.service();
+
+        let (status, body) = send(
+            &service,
+            Method::PUT,
+            r#"{"log": "debug", "debug": "trace"}"#,
+        )
+        .await;
+        assert!(status == StatusCode::OK);
+        assert!(
+            body == serde_json::json!({ "log": "debug", "tracing": "warn", "debug": "trace" })
+        );
+
+        let (_, body) = send(&service, Method::PUT, r#"{"log": "warn"}"#).await;
+        assert!(
+            body == serde_json::json!({ "log": "warn", "tracing": "warn", "debug": "trace" })
+        );
+
+        let (status, _) = send(
+            &service,
+            Method::PUT,
+            r#"{"log": "info", "debug": "hyper=loud"}"#,
+        )
+        .await;
+        assert!(status == StatusCode::BAD_REQUEST);
+
+        let (_, body) = send(&service, Method::PUT, r#"{"debug": null}"#).await;
+        assert!(body == serde_json::json!({ "log": "warn", "tracing": "warn" }));
+    }
+}
@@ -7,19 +7,32 @@ use serde::{
     ser::{SerializeMap, SerializeSeq},
 };
 use serde_json::{Deserializer, Serializer, Value};
-use std::{fmt, io, marker::PhantomData, ops::Deref, str};
-use tracing::{Event, Span, Subscriber};
-use tracing_opentelemetry::OpenTelemetrySpanExt;
+use std::{
+    fmt, io,
+    marker::PhantomData,
+    ops::Deref,
+    str,
+    time::{Duration, Instant},
+};
+use tracing::{
+    Event, Span, Subscriber,
+    field::{Field, Visit},
+    span::{Attributes, Id},
+};
+use tracing_opentelemetry::{OpenTelemetrySpanExt, get_otel_context};
 use tracing_serde::{AsSerde, SerdeMapVisitor};
 use tracing_subscriber::{
     fmt::{
         FmtContext, FormatEvent, FormatFields, FormattedFields,
-        format::Writer,
+        format::{FmtSpan, Writer},
         time::{FormatTime, SystemTime},
     },
+    layer::{Context, Layer},
     registry::{LookupSpan, SpanRef},
 };
 
+use crate::{http::RequestId, util};
+
 /// JSON event formatter for structured logging with OpenTelemetry integration.
 ///
 /// This formatter serializes tracing events into JSON format with additional OpenTelemetry
@@ -42,8 +55,42 @@ use tracing_subscriber::{
 /// - `target`: The module path where the event was recorded
 /// - `trace_id`: OpenTelemetry trace ID (if available)
 /// - `span_id`: OpenTelemetry span ID (if available)
+/// - `request_id`: [`RequestId`] of the span context (if any), see
+///   [`OtelHttpServerLayer::request_id`](crate::middleware::http_server::OtelHttpServerLayer::request_id)
 /// - `spans`: Array of parent spans with their fields
 /// - Additional fields from the log event, including `message`
+///
+/// # Span Lifecycle Records
+///
+/// If the fmt layer is configured to log span events (see
+/// [`with_span_events`](tracing_subscriber::fmt::Layer::with_span_events)), the records
+/// describe the span itself instead of the event fields:
+/// - `span_event`: Lifecycle event (`new`, `enter`, `exit` or `close`), also used as `message`
+/// - `span_name`: Name of the span
+/// - `span`: Name and fields of the span
+/// - `duration_ms`, `busy_ms`, `idle_ms`: Total, busy and idle time of the span in
+///   milliseconds (`close` records only, requires the [`SpanTimingLayer`])
+/// - `spans`, `trace_id`, `span_id`, `request_id`: Same as above, but for the span of the record
+///   rather than the current span
+///
+/// [`init_tracing!`](crate::init_tracing) enables span events in release builds
+/// according to the `OTEL_LOG_SPAN_EVENTS` environment variable
+/// (see [`read_span_events_from_env`]).
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::fmt::{JsonFormat, SpanTimingLayer};
+/// use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt};
+///
+/// let fmt_layer = tracing_subscriber::fmt::layer()
+///     .json()
+///     .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
+///     .event_format(JsonFormat);
+/// let subscriber = tracing_subscriber::registry()
+///     .with(SpanTimingLayer)
+///     .with(fmt_layer);
+/// ```
 pub struct JsonFormat;
 
 impl<S, N> FormatEvent<S, N> for JsonFormat
@@ -61,6 +108,9 @@ where
         SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
 
         let meta = event.metadata();
+        // synthetic events emitted by the fmt layer for span lifecycle records
+        // (see `FmtSpan`) reuse the span metadata and have the span as parent
+        let span_record = event.parent().filter(|_| meta.is_span());
 
         let mut visit = || {
             let mut serializer = Serializer::new(IoWriter(&mut writer));
@@ -69,13 +119,64 @@ where
             serializer.serialize_entry("timestamp", &timestamp)?;
             serializer.serialize_entry("level", &meta.level().as_serde())?;
 
-            // add all event fields to the json object
-            let mut visitor = SerdeMapVisitor::new(serializer);
-            event.record(&mut visitor);
-            serializer = visitor.take_serializer()?;
+            if span_record.is_some() {
+                let mut visitor = SpanRecordVisitor::default();
+                event.record(&mut visitor);
+
+                serializer.serialize_entry("message", &visitor.kind)?;
+                serializer.serialize_entry("span_event", &visitor.kind)?;
+                serializer.serialize_entry("span_name", meta.name())?;
+                if visitor.kind == "close"
+                    && let Some(id) = span_record
+                    && let Some(span) = ctx.span(id)
+                    && let Some(timings) = span.extensions().get::<SpanTimings>()
+                {
+                    let (busy, idle) = timings.busy_idle(Instant::now());
+                    serializer.serialize_entry("duration_ms", &as_ms(busy + idle))?;
+                    serializer.serialize_entry("busy_ms", &as_ms(busy))?;
+                    serializer.serialize_entry("idle_ms", &as_ms(idle))?;
+                }
+            } else {
+                // add all event fields to the json object
+                let mut visitor = SerdeMapVisitor::new(serializer);
+                event.record(&mut visitor);
+                serializer = visitor.take_serializer()?;
+            }
 
             serializer.serialize_entry("target", meta.target())?;
 
+            if let Some(id) = span_record {
+                // lifecycle records describe their own span rather than the current one
+                if let Some(span) = ctx.span(id) {
+                    serializer
+                        .serialize_entry("span", &SpanData(span, PhantomData::<N>))?;
+                }
+                if let Some(span) = ctx.span(id) {
+                    serializer
+                        .serialize_entry("spans", &SpanScope(span, PhantomData::<N>))?;
+                }
+
+                let otel_ctx = tracing::dispatcher::get_default(|dispatch| {
+                    get_otel_context(id, dispatch)
+                });
+                if let Some(otel_ctx) = otel_ctx {
+                    let span_ref = otel_ctx.span();
+                    let span_context = span_ref.span_context();
+                    if span_context.is_valid() {
+                        let trace_id = span_context.trace_id().to_string();
+                        serializer.serialize_entry("trace_id", &trace_id)?;
+
+                        let span_id = span_context.span_id().to_string();
+                        serializer.serialize_entry("span_id", &span_id)?;
+                    }
+                    if let Some(request_id) = RequestId::from_context(&otel_ctx) {
+                        serializer.serialize_entry("request_id", request_id.as_str())?;
+                    }
+                }
+
+                return SerializeMap::end(serializer);
+            }
+
             // extract tracing information from the current span context
             let current_span = Span::current();
             if let Some(id) = current_span.id() {
@@ -93,6 +194,10 @@ where
 
                 let span_id = span_context.span_id().to_string();
                 serializer.serialize_entry("span_id", &span_id)?;
+
+                if let Some(request_id) = RequestId::from_context(&otel_ctx) {
+                    serializer.serialize_entry("request_id", request_id.as_str())?;
+                }
             }
 
             SerializeMap::end(serializer)
@@ -167,6 +272,153 @@ where
     }
 }
 
+/// Collects the fields of span lifecycle records emitted by the fmt layer.
+#[derive(Default)]
+struct SpanRecordVisitor {
+    kind: String,
+}
+
+impl Visit for SpanRecordVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.kind = value.to_owned();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.kind = format!("{value:?}");
+        }
+    }
+}
+
+/// Layer measuring the busy and idle time of spans for the `close` records of
+/// [`JsonFormat`].
+///
+/// The span is busy while it is entered and idle otherwise, from its creation until
+/// it is closed. [`init_tracing!`](crate::init_tracing) installs the layer in release
+/// builds.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::fmt::{JsonFormat, SpanTimingLayer};
+/// use tracing_subscriber::{fmt::format::FmtSpan, layer::SubscriberExt};
+///
+/// let fmt_layer = tracing_subscriber::fmt::layer()
+///     .json()
+///     .with_span_events(FmtSpan::CLOSE)
+///     .event_format(JsonFormat);
+/// let subscriber = tracing_subscriber::registry()
+///     .with(SpanTimingLayer)
+///     .with(fmt_layer);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpanTimingLayer;
+
+/// Busy and idle time of a span, stored in the span extensions.
+#[derive(Debug)]
+struct SpanTimings {
+    busy: Duration,
+    idle: Duration,
+    /// Time of the last transition between busy and idle.
+    last: Instant,
+    closed: bool,
+}
+
+impl SpanTimings {
+    /// Returns the busy and idle time, counting the span as idle until `now` unless
+    /// it has been closed.
+    fn busy_idle(&self, now: Instant) -> (Duration, Duration) {
+        if self.closed {
+            (self.busy, self.idle)
+        } else {
+            (
+                self.busy,
+                self.idle + now.saturating_duration_since(self.last),
+            )
+        }
+    }
+}
+
+impl<S> Layer<S> for SpanTimingLayer
+where
+    S: Subscriber + for<'lookup> LookupSpan<'lookup>,
+{
+    fn on_new_span(&self, _: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanTimings {
+                busy: Duration::ZERO,
+                idle: Duration::ZERO,
+                last: Instant::now(),
+                closed: false,
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(timings) = span.extensions_mut().get_mut::<SpanTimings>()
+        {
+            let now = Instant::now();
+            timings.idle += now.saturating_duration_since(timings.last);
+            timings.last = now;
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id)
+            && let Some(timings) = span.extensions_mut().get_mut::<SpanTimings>()
+        {
+            let now = Instant::now();
+            timings.busy += now.saturating_duration_since(timings.last);
+            timings.last = now;
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id)
+            && let Some(timings) = span.extensions_mut().get_mut::<SpanTimings>()
+        {
+            let now = Instant::now();
+            timings.idle += now.saturating_duration_since(timings.last);
+            timings.last = now;
+            timings.closed = true;
+        }
+    }
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1e3
+}
+
+/// Reads the span lifecycle events to log from the `OTEL_LOG_SPAN_EVENTS` environment
+/// variable.
+///
+/// The variable accepts a comma-separated list of `new`, `enter`, `exit`, `close`,
+/// `active` (enter and exit), `full` (all events) and `none`.
+/// Unknown values are ignored. Returns [`FmtSpan::NONE`] if the variable is not set.
+pub fn read_span_events_from_env() -> FmtSpan {
+    util::env_var("OTEL_LOG_SPAN_EVENTS")
+        .map(|value| parse_span_events(&value))
+        .unwrap_or(FmtSpan::NONE)
+}
+
+fn parse_span_events(value: &str) -> FmtSpan {
+    value
+        .split(',')
+        .map(|event| match event.trim().to_ascii_lowercase().as_str() {
+            "new" => FmtSpan::NEW,
+            "enter" => FmtSpan::ENTER,
+            "exit" => FmtSpan::EXIT,
+            "close" => FmtSpan::CLOSE,
+            "active" => FmtSpan::ACTIVE,
+            "full" => FmtSpan::FULL,
+            _ => FmtSpan::NONE,
+        })
+        .fold(FmtSpan::NONE, |events, event| events | event)
+}
+
 /// The [serde::de::Visitor] which moves entries from one map to another.
 struct SerializeMapVisitor<'a, S: SerializeMap>(&'a mut S);
 
@@ -186,3 +438,164 @@ impl<'de, S: SerializeMap> DeVisitor<'de> for SerializeMapVisitor<'_, S> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use rstest::rstest;
+    use std::sync::{Arc, Mutex};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, fmt::MakeWriter, layer::SubscriberExt};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl MakeWriter<'_> for Buffer {
+        type Writer = Self;
+
+        fn make_writer(&self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[rstest]
+    #[case("", FmtSpan::NONE)]
+    #[case("close", FmtSpan::CLOSE)]
+    #[case("Enter, close", FmtSpan::ENTER | FmtSpan::CLOSE)]
+    #[case("active,unknown", FmtSpan::ACTIVE)]
+    #[case("full", FmtSpan::FULL)]
+    fn test_parse_span_events(#[case] input: &str, #[case] expected: FmtSpan) {
+        assert!(parse_span_events(input) == expected);
+    }
+
+    #[test]
+    fn test_span_lifecycle_records() {
+        let buffer = Buffer::default();
+        let provider = SdkTracerProvider::builder().build();
+        let fmt_layer = tracing_subscriber::fmt::layer()
+            .json()
+            .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
+            .event_format(JsonFormat)
+            .with_writer(buffer.clone());
+        let subscriber = Registry::default()
+            .with(SpanTimingLayer)
+            .with(fmt_layer)
+            .with(OpenTelemetryLayer::new(provider.tracer("test")));
+
+        let trace_id = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", user = "alice");
+            let trace_id = span.context().span().span_context().trace_id();
+            span.in_scope(|| {
+                tracing::info!("inside");
+                std::thread::sleep(Duration::from_millis(2));
+            });
+            trace_id.to_string()
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(records.len() == 3);
+        let (enter, event, close) = (&records[0], &records[1], &records[2]);
+        assert!(enter["span_event"] == "enter");
+        assert!(enter["span_name"] == "request");
+        assert!(enter["trace_id"] == trace_id.as_str());
+        assert!(event["message"] == "inside");
+        assert!(event.get("span_event").is_none());
+        assert!(close["span_event"] == "close");
+        assert!(
+            close["span"] == serde_json::json!({ "name": "request", "user": "alice" })
+        );
+        assert!(close["trace_id"] == trace_id.as_str());
+        let busy = close["busy_ms"].as_f64().unwrap();
+        let idle = close["idle_ms"].as_f64().unwrap();
+        let duration = close["duration_ms"].as_f64().unwrap();
+        assert!(busy >= 2.0);
+        assert!(idle > 0.0);
+        assert!((duration - busy - idle).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_span_timings() {
+        let start = Instant::now();
+        let timings = SpanTimings {
+            busy: Duration::from_millis(3),
+            idle: Duration::from_millis(5),
+            last: start,
+            closed: false,
+        };
+        let now = start + Duration::from_millis(2);
+
+        assert!(
+            timings.busy_idle(now)
+                == (Duration::from_millis(3), Duration::from_millis(7))
+        );
+
+        let timings = SpanTimings {
+            closed: true,
+            ..timings
+        };
+        assert!(
+            timings.busy_idle(now)
+                == (Duration::from_millis(3), Duration::from_millis(5))
+        );
+    }
+
+    #[test]
+    fn test_request_id() {
+        use crate::http::{RequestId, RequestIdCell};
+
+        let buffer = Buffer::default();
+        let provider = SdkTracerProvider::builder().build();
+        let fmt_layer = tracing_subscriber::fmt::layer()
+            .json()
+            .with_span_events(FmtSpan::CLOSE)
+            .event_format(JsonFormat)
+            .with_writer(buffer.clone());
+        let subscriber = Registry::default()
+            .with(fmt_layer)
+            .with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let request_id =
+            RequestId::new(http::HeaderName::from_static("x-request-id"), "abc").unwrap();
+        let cell = RequestIdCell::default();
+        cell.set(request_id);
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            let _ = span.set_parent(opentelemetry::Context::new().with_value(cell));
+            drop(span.in_scope(|| tracing::info_span!("child")));
+            drop(tracing::info_span!("other"));
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records = output
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(records.len() == 3);
+        let (child, other, request) = (&records[0], &records[1], &records[2]);
+        assert!(child["span_name"] == "child");
+        assert!(child["request_id"] == "abc");
+        assert!(other["span_name"] == "other");
+        assert!(other.get("request_id").is_none());
+        assert!(request["span_name"] == "request");
+        assert!(request["request_id"] == "abc");
+    }
+}
@@ -7,9 +7,11 @@
 use pin_project_lite::pin_project;
 use std::{
     future::Future,
+    panic::{self, AssertUnwindSafe},
     pin::Pin,
     task::{Context as TaskContext, Poll, ready},
 };
+use tracing::Span;
 
 /// Trait for handling the completion of instrumented futures.
 ///
@@ -23,6 +25,22 @@ pub trait InstrumentedFutureContext<T> {
     ///
     /// - `result`: Reference to the result produced by the future
     fn on_result(self, result: &T);
+
+    /// Whether the instrumented future catches the panics of the wrapped future to call
+    /// [`on_panic`](Self::on_panic), `false` by default.
+    ///
+    /// Every poll of the wrapped future is then run in [`std::panic::catch_unwind`].
+    const CATCH_PANICS: bool = false;
+
+    /// Called when the instrumented future panics, once the future is dropped and
+    /// before the panic is resumed, if [`CATCH_PANICS`](Self::CATCH_PANICS) is `true`.
+    ///
+    /// Does nothing by default.
+    fn on_panic(self)
+    where
+        Self: Sized,
+    {
+    }
 }
 
 pin_project! {
@@ -112,22 +130,74 @@ where
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
         // First, try to get the ready value of the future
-        let ready = match self.as_mut().project() {
-            InstrumentedFutureProj::Pending { future, context: _ } => {
-                ready!(future.poll(cx))
+        let poll = match self.as_mut().project() {
+            InstrumentedFutureProj::Pending { future, context: _ } if C::CATCH_PANICS => {
+                panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx)))
             }
+            InstrumentedFutureProj::Pending { future, context: _ } => Ok(future.poll(cx)),
             InstrumentedFutureProj::Complete => panic!("future polled after completion"),
         };
+        let ready = match poll {
+            Ok(poll) => ready!(poll),
+            Err(payload) => {
+                self.complete().on_panic();
+                panic::resume_unwind(payload);
+            }
+        };
+
+        self.complete().on_result(&ready);
+        Poll::Ready(ready)
+    }
+}
 
-        // If we got the ready value, we first drop the future: this ensures that the
-        // OpenTelemetry span attached to it is closed and included in the subsequent flush.
-        let context = match self.project_replace(InstrumentedFuture::Complete) {
+impl<F, C> InstrumentedFuture<F, C>
+where
+    F: Future,
+    C: InstrumentedFutureContext<F::Output>,
+{
+    /// Drops the future and returns its context.
+    ///
+    /// The future is dropped first: this ensures that the OpenTelemetry span attached
+    /// to it is closed and included in a subsequent flush by the context.
+    fn complete(self: Pin<&mut Self>) -> C {
+        match self.project_replace(InstrumentedFuture::Complete) {
             InstrumentedFutureOwn::Pending { future: _, context } => context,
             InstrumentedFutureOwn::Complete => unreachable!("future already completed"),
-        };
+        }
+    }
+}
 
-        context.on_result(&ready);
-        Poll::Ready(ready)
+pin_project! {
+    /// A future wrapper recording panics on a span.
+    ///
+    /// Panics raised while the wrapped future is polled are recorded on the span as
+    /// `exception` events with their message and backtrace, marking the span as
+    /// failed. This includes panics caught by the wrapped future itself, e.g. by a
+    /// `catch_unwind` in an inner service. Panics propagated by the wrapped future are
+    /// resumed once recorded.
+    pub struct RecordPanic<F> {
+        #[pin]
+        future: F,
+        span: Span,
+    }
+}
+
+impl<F> RecordPanic<F> {
+    /// Creates a new future recording the panics of `future` on `span`.
+    pub fn new(future: F, span: Span) -> Self {
+        Self { future, span }
+    }
+}
+
+impl<F: Future> Future for RecordPanic<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match crate::panic::record_panics(this.span, || this.future.poll(cx)) {
+            Ok(poll) => poll,
+            Err(panic) => panic.resume(),
+        }
     }
 }
 
@@ -145,6 +215,13 @@ mod tests {
             assert!(counter.fetch_add(1, Ordering::AcqRel) == expected_count);
             assert!(result == &expected_result);
         }
+
+        const CATCH_PANICS: bool = true;
+
+        fn on_panic(self) {
+            let Self(counter, expected_count, _) = self;
+            assert!(counter.fetch_add(1, Ordering::AcqRel) == expected_count);
+        }
     }
 
     #[tokio::test]
@@ -160,4 +237,17 @@ mod tests {
         assert!(hook_called.load(Ordering::Acquire) == 2);
         assert!(res == 42);
     }
+
+    #[tokio::test]
+    async fn test_panicking_future() {
+        static HOOK_CALLED: AtomicUsize = AtomicUsize::new(0);
+        let fut1 = async { panic!("future failed") };
+        let fut2 = InstrumentedFuture::new(fut1, TestContext(&HOOK_CALLED, 0, 42));
+        let fut3 = InstrumentedFuture::new(fut2, TestContext(&HOOK_CALLED, 1, 42));
+
+        let result = tokio::spawn(fut3).await;
+
+        assert!(HOOK_CALLED.load(Ordering::Acquire) == 2);
+        assert!(result.is_err_and(|err| err.is_panic()));
+    }
 }
@@ -4,9 +4,33 @@
 // which is licensed under CC0 1.0 Universal
 // https://github.com/davidB/tracing-opentelemetry-instrumentation-sdk/blob/d3609ac2cc699d3a24fbf89754053cc8e938e3bf/LICENSE
 
-use opentelemetry::Context;
+use http::{HeaderMap, HeaderName, HeaderValue};
 use opentelemetry::propagation::{Extractor, Injector};
-use tracing_opentelemetry_instrumentation_sdk as otel;
+use opentelemetry::{Array, Context, KeyValue, StringValue, Value};
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{semconv, util};
+
+/// Headers whose values are never recorded, even if they are in the allow-list.
+const REDACTED_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+const REDACTED_VALUE: &str = "REDACTED";
+
+const SERVER_REQUEST_HEADERS_ENV: &str =
+    "OTEL_INSTRUMENTATION_HTTP_SERVER_CAPTURE_HEADERS_REQUEST";
+const SERVER_RESPONSE_HEADERS_ENV: &str =
+    "OTEL_INSTRUMENTATION_HTTP_SERVER_CAPTURE_HEADERS_RESPONSE";
+const CLIENT_REQUEST_HEADERS_ENV: &str =
+    "OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_REQUEST";
+const CLIENT_RESPONSE_HEADERS_ENV: &str =
+    "OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_RESPONSE";
 
 /// HTTP header injector for OpenTelemetry context propagation.
 ///
@@ -125,7 +149,7 @@ pub fn inject_context_on_context(context: &Context, headers: &mut http::HeaderMa
 pub fn inject_context(headers: &mut http::HeaderMap) {
     let mut injector = HeaderInjector(headers);
     opentelemetry::global::get_text_map_propagator(|propagator| {
-        propagator.inject_context(&otel::find_current_context(), &mut injector);
+        propagator.inject_context(&crate::baggage::current_context(), &mut injector);
     });
 }
 
@@ -161,3 +185,329 @@ pub fn extract_context(headers: &http::HeaderMap) -> Context {
         propagator.extract(&extractor)
     })
 }
+
+/// Identifier of an HTTP request, used to correlate logs, e.g. with support tickets.
+///
+/// Request ids are read from an inbound header or generated by the
+/// [`OtelHttpServerLayer`](crate::middleware::http_server::OtelHttpServerLayer) if
+/// configured with a request id header. The layer stores the id in the request
+/// extensions and in the context of the server span, so that it is available to
+/// [`RequestId::current`] in all the spans of the request. The instrumented HTTP
+/// clients send the request id of the current context in the same header, and
+/// [`JsonFormat`](crate::fmt::JsonFormat) records it as `request_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId {
+    header_name: HeaderName,
+    value: HeaderValue,
+}
+
+impl RequestId {
+    /// Creates a request id sent in the `header_name` header.
+    ///
+    /// Returns `None` if the value is empty or is not a valid header value.
+    pub fn new(header_name: HeaderName, value: impl AsRef<str>) -> Option<Self> {
+        let value = value.as_ref().trim();
+        if value.is_empty() {
+            return None;
+        }
+        let value = HeaderValue::from_str(value).ok()?;
+        // header values may contain bytes which are not visible ASCII characters
+        value.to_str().ok()?;
+        Some(Self { header_name, value })
+    }
+
+    /// Returns the name of the header carrying the request id.
+    pub fn header_name(&self) -> &HeaderName {
+        &self.header_name
+    }
+
+    /// Returns the request id.
+    pub fn as_str(&self) -> &str {
+        self.value.to_str().unwrap_or_default()
+    }
+
+    /// Returns the request id of the current span, or of the current context.
+    pub fn current() -> Option<Self> {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        Self::from_context(&tracing::Span::current().context())
+            .or_else(|| Self::from_context(&Context::current()))
+    }
+
+    /// Returns the request id of an OpenTelemetry context.
+    pub fn from_context(context: &Context) -> Option<Self> {
+        context.get::<RequestIdCell>()?.0.get().cloned()
+    }
+
+    /// Inserts the request id in the headers, unless the header is already set.
+    pub(crate) fn insert(&self, headers: &mut HeaderMap) {
+        if !headers.contains_key(&self.header_name) {
+            headers.insert(self.header_name.clone(), self.value.clone());
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Request id stored in an OpenTelemetry context.
+///
+/// The request id may be generated from the trace id of a span, which is only known
+/// once the span has started with its parent context, so the context holds a cell set
+/// afterwards.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestIdCell(Arc<OnceLock<RequestId>>);
+
+impl RequestIdCell {
+    #[cfg(any(test, feature = "http-server"))]
+    pub(crate) fn set(&self, request_id: RequestId) {
+        let _ = self.0.set(request_id);
+    }
+}
+
+/// Injects the [`RequestId`] of an OpenTelemetry context into HTTP headers.
+///
+/// Does nothing if the context has no request id, or if the request id header is
+/// already set. The instrumented HTTP clients call it with the context of their
+/// client span.
+///
+/// # Examples
+///
+/// ```rust
+/// use http::HeaderMap;
+/// use telemetry_rust::http::{RequestId, inject_request_id};
+///
+/// let mut headers = HeaderMap::new();
+/// if let Some(request_id) = RequestId::current() {
+///     println!("request id: {request_id}");
+/// }
+/// inject_request_id(&opentelemetry::Context::current(), &mut headers);
+/// ```
+pub fn inject_request_id(context: &Context, headers: &mut HeaderMap) {
+    if let Some(request_id) = RequestId::from_context(context) {
+        request_id.insert(headers);
+    }
+}
+
+/// Allow-list of HTTP headers recorded as span attributes.
+///
+/// Request headers are recorded as `http.request.header.<name>` and response headers
+/// as `http.response.header.<name>`, with all the values of the header as a string
+/// array. Header names are case-insensitive and recorded lowercase.
+///
+/// The values of `authorization`, `proxy-authorization`, `cookie` and `set-cookie`
+/// are always recorded as `REDACTED`.
+///
+/// The allow-lists can also be configured with comma-separated header names in
+/// environment variables, see [`HeaderCapture::server_from_env`] and
+/// [`HeaderCapture::client_from_env`].
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::http::HeaderCapture;
+///
+/// let capture = HeaderCapture::server_from_env()
+///     .request_headers(["x-request-id", "content-type"])
+///     .response_headers(["cache-control"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderCapture {
+    request: Arc<[HeaderName]>,
+    response: Arc<[HeaderName]>,
+}
+
+impl HeaderCapture {
+    /// Creates an empty allow-list, capturing no header.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the allow-lists of server spans from the
+    /// `OTEL_INSTRUMENTATION_HTTP_SERVER_CAPTURE_HEADERS_REQUEST` and
+    /// `OTEL_INSTRUMENTATION_HTTP_SERVER_CAPTURE_HEADERS_RESPONSE` environment variables.
+    pub fn server_from_env() -> Self {
+        Self::from_env(SERVER_REQUEST_HEADERS_ENV, SERVER_RESPONSE_HEADERS_ENV)
+    }
+
+    /// Creates the allow-lists of client spans from the
+    /// `OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_REQUEST` and
+    /// `OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_RESPONSE` environment variables.
+    pub fn client_from_env() -> Self {
+        Self::from_env(CLIENT_REQUEST_HEADERS_ENV, CLIENT_RESPONSE_HEADERS_ENV)
+    }
+
+    fn from_env(request_key: &str, response_key: &str) -> Self {
+        let names = |key| {
+            util::env_var(key)
+                .map(|value| value.split(',').map(str::to_owned).collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+        Self::new()
+            .request_headers(names(request_key))
+            .response_headers(names(response_key))
+    }
+
+    /// Adds request headers to capture. Invalid header names are ignored.
+    pub fn request_headers(
+        self,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        Self {
+            request: extend(&self.request, names),
+            ..self
+        }
+    }
+
+    /// Adds response headers to capture. Invalid header names are ignored.
+    pub fn response_headers(
+        self,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Self {
+        Self {
+            response: extend(&self.response, names),
+            ..self
+        }
+    }
+
+    /// Returns `true` if no header is captured.
+    pub fn is_empty(&self) -> bool {
+        self.request.is_empty() && self.response.is_empty()
+    }
+
+    /// Returns the span attributes of the captured request headers, e.g. to record
+    /// them on spans of custom instrumentations.
+    pub fn request_attributes(&self, headers: &HeaderMap) -> Vec<KeyValue> {
+        capture(semconv::HTTP_REQUEST_HEADER, &self.request, headers)
+    }
+
+    /// Returns the span attributes of the captured response headers.
+    pub fn response_attributes(&self, headers: &HeaderMap) -> Vec<KeyValue> {
+        capture(semconv::HTTP_RESPONSE_HEADER, &self.response, headers)
+    }
+}
+
+fn extend(
+    current: &[HeaderName],
+    names: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Arc<[HeaderName]> {
+    let mut result = current.to_vec();
+    for name in names {
+        if let Ok(name) = HeaderName::from_bytes(name.as_ref().trim().as_bytes())
+            && !result.contains(&name)
+        {
+            result.push(name);
+        }
+    }
+    result.into()
+}
+
+fn capture(prefix: &str, names: &[HeaderName], headers: &HeaderMap) -> Vec<KeyValue> {
+    names
+        .iter()
+        .filter(|name| headers.contains_key(*name))
+        .map(|name| {
+            let values = if REDACTED_HEADERS.contains(&name.as_str()) {
+                vec![StringValue::from(REDACTED_VALUE)]
+            } else {
+                headers
+                    .get_all(name)
+                    .iter()
+                    .map(|value| {
+                        String::from_utf8_lossy(value.as_bytes())
+                            .into_owned()
+                            .into()
+                    })
+                    .collect()
+            };
+            KeyValue::new(
+                format!("{prefix}.{name}"),
+                Value::Array(Array::String(values)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("abc-123", Some("abc-123"))]
+    #[case(" abc ", Some("abc"))]
+    #[case("", None)]
+    #[case("caf\u{e9}", None)]
+    #[case("line\nbreak", None)]
+    fn test_request_id(#[case] value: &str, #[case] expected: Option<&str>) {
+        let request_id = RequestId::new(HeaderName::from_static("x-request-id"), value);
+
+        assert!(request_id.as_ref().map(RequestId::as_str) == expected);
+    }
+
+    #[test]
+    fn test_inject_request_id() {
+        let request_id =
+            RequestId::new(HeaderName::from_static("x-correlation-id"), "abc").unwrap();
+        let cell = RequestIdCell::default();
+        let context = Context::new().with_value(cell.clone());
+        let mut headers = HeaderMap::new();
+
+        inject_request_id(&context, &mut headers);
+        assert!(headers.is_empty());
+
+        cell.set(request_id.clone());
+        inject_request_id(&context, &mut headers);
+        assert!(headers["x-correlation-id"] == "abc");
+        assert!(RequestId::from_context(&context) == Some(request_id));
+
+        headers.insert("x-correlation-id", "other".parse().unwrap());
+        inject_request_id(&context, &mut headers);
+        assert!(headers["x-correlation-id"] == "other");
+    }
+
+    #[test]
+    fn test_header_capture() {
+        let capture = HeaderCapture::new()
+            .request_headers(["X-Request-Id", "Authorization", "accept", "invalid name"])
+            .response_headers(["Set-Cookie"]);
+        let mut headers = HeaderMap::new();
+        headers.append("x-request-id", "abc".parse().unwrap());
+        headers.append("accept", "text/html".parse().unwrap());
+        headers.append("accept", "application/json".parse().unwrap());
+        headers.append("authorization", "Bearer secret".parse().unwrap());
+        headers.append("set-cookie", "session=secret".parse().unwrap());
+        headers.append("x-other", "other".parse().unwrap());
+
+        let string_array = |values: &[&'static str]| {
+            Value::Array(Array::String(values.iter().map(|v| (*v).into()).collect()))
+        };
+        assert!(
+            capture.request_attributes(&headers)
+                == [
+                    KeyValue::new(
+                        "http.request.header.x-request-id",
+                        string_array(&["abc"])
+                    ),
+                    KeyValue::new(
+                        "http.request.header.authorization",
+                        string_array(&["REDACTED"])
+                    ),
+                    KeyValue::new(
+                        "http.request.header.accept",
+                        string_array(&["text/html", "application/json"])
+                    ),
+                ]
+        );
+        assert!(
+            capture.response_attributes(&headers)
+                == [KeyValue::new(
+                    "http.response.header.set-cookie",
+                    string_array(&["REDACTED"])
+                )]
+        );
+    }
+}
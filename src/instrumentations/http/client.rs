@@ -1,21 +1,24 @@
-use std::{error::Error, net::SocketAddr};
+use std::{error::Error, net::SocketAddr, sync::LazyLock};
 
 use http::{HeaderMap, Method, uri::Authority};
 use opentelemetry::{
     global,
     trace::{SpanKind, Status, TraceContextExt, Tracer},
 };
-use tracing::Span;
 use tracing_opentelemetry_instrumentation_sdk::http::http_flavor;
 
 use crate::{
-    Context, KeyValue, OpenTelemetrySpanExt, Value, future::InstrumentedFutureContext,
-    semconv, util::as_attribute,
+    Context, KeyValue, Value, baggage, future::InstrumentedFutureContext,
+    http::HeaderCapture, semconv, util::as_attribute,
 };
 
 const OTHER_HTTP_METHOD: &str = "_OTHER";
 const HTTP_SPAN_NAME: &str = "HTTP";
 
+/// Headers captured by clients without a [`HeaderCapture`] of their own.
+static DEFAULT_HEADER_CAPTURE: LazyLock<HeaderCapture> =
+    LazyLock::new(HeaderCapture::client_from_env);
+
 pub(crate) trait UrlParts {
     fn full_url(&self) -> Option<impl Into<Value>>;
     fn path(&self) -> Option<impl Into<Value>>;
@@ -32,11 +35,13 @@ pub(crate) trait UrlParts {
 /// its parent.
 pub(crate) struct HttpClientSpan {
     context: Context,
+    header_capture: HeaderCapture,
 }
 
 pub(crate) struct HttpClientSpanBuilder {
     attributes: Vec<KeyValue>,
     span_name: &'static str,
+    header_capture: HeaderCapture,
 }
 
 impl HttpClientSpanBuilder {
@@ -100,13 +105,28 @@ impl HttpClientSpanBuilder {
         Self {
             attributes: attributes.into_iter().flatten().collect(),
             span_name,
+            header_capture: HeaderCapture::new(),
         }
     }
 
+    /// Records the request headers allowed by `header_capture`, or by the client
+    /// environment variables if `None`, and keeps it to record response headers.
+    pub(crate) fn capture_headers(
+        mut self,
+        header_capture: Option<&HeaderCapture>,
+        headers: &HeaderMap,
+    ) -> Self {
+        let header_capture = header_capture.unwrap_or(&DEFAULT_HEADER_CAPTURE);
+        self.attributes
+            .extend(header_capture.request_attributes(headers));
+        self.header_capture = header_capture.clone();
+        self
+    }
+
     pub(crate) fn start(self, parent_cx: &Option<Context>) -> HttpClientSpan {
         match parent_cx {
             Some(cx) => self.start_with_context(cx),
-            None => self.start_with_context(&Span::current().context()),
+            None => self.start_with_context(&baggage::current_context()),
         }
     }
 
@@ -120,6 +140,7 @@ impl HttpClientSpanBuilder {
 
         HttpClientSpan {
             context: parent_cx.with_span(span),
+            header_capture: self.header_capture,
         }
     }
 }
@@ -143,6 +164,10 @@ impl HttpClientSpan {
             http_flavor(response.version()).into_owned(),
         ));
 
+        for attribute in self.header_capture.response_attributes(response.headers()) {
+            span.set_attribute(attribute);
+        }
+
         if let Some(addr) = response.remote_addr() {
             span.set_attribute(KeyValue::new(
                 semconv::NETWORK_PEER_ADDRESS,
@@ -192,6 +217,7 @@ fn semantic_method(method: &Method) -> &'static str {
 pub(crate) trait HttpResponse {
     fn status(&self) -> http::StatusCode;
     fn version(&self) -> http::Version;
+    fn headers(&self) -> &HeaderMap;
     fn remote_addr(&self) -> Option<SocketAddr> {
         None
     }
@@ -205,6 +231,10 @@ impl<T> HttpResponse for http::Response<T> {
     fn version(&self) -> http::Version {
         self.version()
     }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
 }
 
 pub(crate) trait HttpError: Error + 'static {
@@ -32,6 +32,7 @@
 
 use std::error::Error as StdError;
 use std::future::Future;
+use std::sync::Arc;
 
 use hyper::{
     Request, Response,
@@ -42,10 +43,15 @@ use hyper_util::client::legacy;
 use crate::{
     Context,
     future::InstrumentedFuture,
-    http,
-    instrumentations::http::client::{HttpClientSpanBuilder, HttpError},
+    http::{self, HeaderCapture},
+    instrumentations::http::{
+        PropagationPolicy,
+        client::{HttpClientSpanBuilder, HttpError},
+    },
 };
 
+use super::should_propagate_request;
+
 impl HttpError for legacy::Error {
     fn error_type(&self) -> &'static str {
         if self.is_connect() {
@@ -93,6 +99,8 @@ impl<C, B> HyperLegacyClientInstrument for legacy::Client<C, B> {
 pub struct InstrumentedLegacyClient<C, B> {
     inner: legacy::Client<C, B>,
     context: Option<Context>,
+    propagation_policy: Option<Arc<PropagationPolicy>>,
+    header_capture: Option<HeaderCapture>,
 }
 
 impl<C, B> InstrumentedLegacyClient<C, B> {
@@ -101,6 +109,8 @@ impl<C, B> InstrumentedLegacyClient<C, B> {
         Self {
             inner,
             context: None,
+            propagation_policy: None,
+            header_capture: None,
         }
     }
 
@@ -116,6 +126,24 @@ impl<C, B> InstrumentedLegacyClient<C, B> {
         self
     }
 
+    /// Sets the [`PropagationPolicy`] deciding whether trace context is injected into
+    /// requests sent by this wrapper, overriding the global policy.
+    pub fn propagation_policy(
+        mut self,
+        policy: impl Into<Arc<PropagationPolicy>>,
+    ) -> Self {
+        self.propagation_policy = Some(policy.into());
+        self
+    }
+
+    /// Sets the [`HeaderCapture`] deciding which headers are recorded on client spans
+    /// of requests sent by this wrapper, overriding the `OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_*`
+    /// environment variables.
+    pub fn capture_headers(mut self, header_capture: HeaderCapture) -> Self {
+        self.header_capture = Some(header_capture);
+        self
+    }
+
     /// Returns the wrapped legacy hyper client.
     pub fn into_inner(self) -> legacy::Client<C, B> {
         self.inner
@@ -130,6 +158,8 @@ where
         Self {
             inner: self.inner.clone(),
             context: self.context.clone(),
+            propagation_policy: self.propagation_policy.clone(),
+            header_capture: self.header_capture.clone(),
         }
     }
 }
@@ -147,9 +177,14 @@ where
         &self,
         mut request: Request<B>,
     ) -> impl Future<Output = Result<Response<Incoming>, legacy::Error>> + '_ {
-        let span = HttpClientSpanBuilder::from(&request).start(&self.context);
+        let span = HttpClientSpanBuilder::from(&request)
+            .capture_headers(self.header_capture.as_ref(), request.headers())
+            .start(&self.context);
 
-        http::inject_context_on_context(span.context(), request.headers_mut());
+        if should_propagate_request(self.propagation_policy.as_deref(), &request) {
+            http::inject_context_on_context(span.context(), request.headers_mut());
+            http::inject_request_id(span.context(), request.headers_mut());
+        }
 
         let future = self.inner.request(request);
         InstrumentedFuture::new(future, span)
@@ -173,7 +208,11 @@ where
 #[cfg(test)]
 mod tests {
     use super::HyperLegacyClientInstrument;
-    use crate::{Context, instrumentations::http::test_utils::*, semconv};
+    use crate::{
+        Context,
+        instrumentations::http::{PropagationPolicy, test_utils::*},
+        semconv,
+    };
     use assert2::assert;
     use axum::http::StatusCode;
     use bytes::Bytes;
@@ -233,6 +272,48 @@ mod tests {
         assert!(string_attr(span, semconv::NETWORK_PROTOCOL_VERSION).is_some());
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn legacy_client_skips_propagation_to_untrusted_destinations() {
+        let telemetry = configure_test_tracing();
+        let server = spawn_server().await;
+        let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new())
+            .build_http::<Empty<Bytes>>()
+            .instrument()
+            .propagation_policy(PropagationPolicy::new().allow_scheme("https"));
+
+        let response = client
+            .get(format!("{}/ok", server.base_url).parse().unwrap())
+            .await
+            .unwrap();
+
+        assert!(response.status() == StatusCode::OK);
+
+        let spans = force_flush_and_get_spans(&telemetry);
+        let span = find_span(&spans, "GET");
+
+        assert!(span.span_kind == SpanKind::Client);
+        assert!(server.state.traceparent_for("/ok").is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn legacy_client_propagates_request_id() {
+        let _telemetry = configure_test_tracing();
+        let server = spawn_server().await;
+        let client = hyper_util::client::legacy::Client::builder(TokioExecutor::new())
+            .build_http::<Empty<Bytes>>()
+            .instrument()
+            .context(&request_id_context("abc"));
+
+        client
+            .get(format!("{}/ok", server.base_url).parse().unwrap())
+            .await
+            .unwrap();
+
+        assert!(server.state.request_id_for("/ok") == Some("abc".to_owned()));
+    }
+
     #[tokio::test]
     #[serial]
     async fn legacy_client_uses_explicit_parent_context_when_provided() {
@@ -37,9 +37,17 @@
 //! # }
 //! ```
 
+use http::uri::Authority;
+use std::sync::Arc;
+
 use crate::{
     Context, Value,
-    instrumentations::http::client::{HttpClientSpanBuilder, HttpError, UrlParts},
+    http::HeaderCapture,
+    instrumentations::http::{
+        PropagationPolicy,
+        client::{HttpClientSpanBuilder, HttpError, UrlParts},
+        policy::{Destination, should_propagate},
+    },
 };
 
 /// Async instrumentation helpers for `hyper_util::client::legacy::Client`.
@@ -107,6 +115,28 @@ impl<B> From<&hyper::Request<B>> for HttpClientSpanBuilder {
     }
 }
 
+/// Returns `true` if trace context should be injected into the request, using the
+/// `Host` header as destination host and port for requests with a relative URI.
+pub(crate) fn should_propagate_request<B>(
+    policy: Option<&PropagationPolicy>,
+    request: &hyper::Request<B>,
+) -> bool {
+    let uri = request.uri();
+    let mut destination = Destination::from_uri(uri);
+    let authority = uri
+        .authority()
+        .is_none()
+        .then(|| request.headers().get(hyper::header::HOST))
+        .flatten()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<Authority>().ok());
+    if let Some(authority) = &authority {
+        destination.host = Some(authority.host());
+        destination.port = authority.port_u16();
+    }
+    should_propagate(policy, &destination)
+}
+
 /// A trait for creating instrumented hyper connection senders with OpenTelemetry tracing.
 pub trait HyperSendRequestInstrument
 where
@@ -137,6 +167,8 @@ impl<B> HyperSendRequestInstrument for hyper::client::conn::http2::SendRequest<B
 pub struct InstrumentedSendRequest<S> {
     inner: S,
     context: Option<Context>,
+    propagation_policy: Option<Arc<PropagationPolicy>>,
+    header_capture: Option<HeaderCapture>,
 }
 
 impl<S> InstrumentedSendRequest<S> {
@@ -145,6 +177,8 @@ impl<S> InstrumentedSendRequest<S> {
         Self {
             inner,
             context: None,
+            propagation_policy: None,
+            header_capture: None,
         }
     }
 
@@ -160,6 +194,24 @@ impl<S> InstrumentedSendRequest<S> {
         self
     }
 
+    /// Sets the [`PropagationPolicy`] deciding whether trace context is injected into
+    /// requests sent by this wrapper, overriding the global policy.
+    pub fn propagation_policy(
+        mut self,
+        policy: impl Into<Arc<PropagationPolicy>>,
+    ) -> Self {
+        self.propagation_policy = Some(policy.into());
+        self
+    }
+
+    /// Sets the [`HeaderCapture`] deciding which headers are recorded on client spans
+    /// of requests sent by this wrapper, overriding the `OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_*`
+    /// environment variables.
+    pub fn capture_headers(mut self, header_capture: HeaderCapture) -> Self {
+        self.header_capture = Some(header_capture);
+        self
+    }
+
     /// Returns the wrapped hyper sender.
     pub fn into_inner(self) -> S {
         self.inner
@@ -174,6 +226,8 @@ where
         Self {
             inner: self.inner.clone(),
             context: self.context.clone(),
+            propagation_policy: self.propagation_policy.clone(),
+            header_capture: self.header_capture.clone(),
         }
     }
 }
@@ -191,7 +245,7 @@ macro_rules! impl_instrumented_send_request {
                 client::conn::$http::SendRequest,
             };
 
-            use super::InstrumentedSendRequest;
+            use super::{InstrumentedSendRequest, should_propagate_request};
             use crate::{
                 future::InstrumentedFuture, http,
                 instrumentations::http::client::HttpClientSpanBuilder,
@@ -226,12 +280,20 @@ macro_rules! impl_instrumented_send_request {
                     &mut self,
                     mut request: Request<B>,
                 ) -> impl Future<Output = Result<Response<Incoming>>> + '_ {
-                    let span = HttpClientSpanBuilder::from(&request).start(&self.context);
-
-                    http::inject_context_on_context(
-                        span.context(),
-                        request.headers_mut(),
-                    );
+                    let span = HttpClientSpanBuilder::from(&request)
+                        .capture_headers(self.header_capture.as_ref(), request.headers())
+                        .start(&self.context);
+
+                    if should_propagate_request(
+                        self.propagation_policy.as_deref(),
+                        &request,
+                    ) {
+                        http::inject_context_on_context(
+                            span.context(),
+                            request.headers_mut(),
+                        );
+                        http::inject_request_id(span.context(), request.headers_mut());
+                    }
 
                     let future = self.inner.send_request(request);
                     InstrumentedFuture::new(future, span)
@@ -249,7 +311,11 @@ impl_instrumented_send_request!(http2);
 #[cfg(all(test, any(feature = "hyper-http1", feature = "hyper-http2")))]
 mod tests {
     use super::HyperSendRequestInstrument;
-    use crate::{Context, instrumentations::http::test_utils::*, semconv};
+    use crate::{
+        Context,
+        instrumentations::http::{PropagationPolicy, test_utils::*},
+        semconv,
+    };
     use axum::http::StatusCode;
     use bytes::Bytes;
     use http_body_util::Empty;
@@ -267,6 +333,7 @@ mod tests {
     mod http1 {
         use super::*;
         use assert2::assert;
+        use rstest::rstest;
 
         #[tokio::test]
         #[serial]
@@ -480,6 +547,71 @@ mod tests {
             );
         }
 
+        #[rstest]
+        #[case(PropagationPolicy::new().deny_host("127.0.0.1"))]
+        #[case(PropagationPolicy::new().deny_scheme("http"))]
+        #[case(PropagationPolicy::new().allow_url_prefix("http://127.0.0.1/"))]
+        #[tokio::test]
+        #[serial]
+        async fn skips_propagation_for_origin_form_uri_to_untrusted_destinations(
+            #[case] policy: PropagationPolicy,
+        ) {
+            let _telemetry = configure_test_tracing();
+            let server = spawn_server().await;
+            let io = TokioIo::new(TcpStream::connect(server.addr).await.unwrap());
+            let (send_request, connection) =
+                hyper::client::conn::http1::handshake(io).await.unwrap();
+
+            tokio::spawn(async move {
+                connection.await.unwrap();
+            });
+
+            let mut send_request = send_request.instrument().propagation_policy(policy);
+            let response = send_request
+                .send_request(
+                    Request::builder()
+                        .uri("/ok")
+                        .header(HOST, server.authority())
+                        .body(Empty::<Bytes>::new())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert!(response.status() == StatusCode::OK);
+            assert!(server.state.traceparent_for("/ok").is_none());
+        }
+
+        #[tokio::test]
+        #[serial]
+        async fn propagates_request_id() {
+            let _telemetry = configure_test_tracing();
+            let server = spawn_server().await;
+            let io = TokioIo::new(TcpStream::connect(server.addr).await.unwrap());
+            let (send_request, connection) =
+                hyper::client::conn::http1::handshake(io).await.unwrap();
+
+            tokio::spawn(async move {
+                connection.await.unwrap();
+            });
+
+            let mut send_request = send_request
+                .instrument()
+                .context(&request_id_context("abc"));
+            send_request
+                .send_request(
+                    Request::builder()
+                        .uri(format!("{}/ok", server.base_url))
+                        .header(HOST, server.authority())
+                        .body(Empty::<Bytes>::new())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+
+            assert!(server.state.request_id_for("/ok") == Some("abc".to_owned()));
+        }
+
         #[tokio::test]
         #[serial]
         async fn uses_explicit_parent_context_when_provided() {
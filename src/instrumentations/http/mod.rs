@@ -1,6 +1,15 @@
 //! HTTP client instrumentation utilities.
 
+#[cfg(any(
+    feature = "reqwest",
+    feature = "hyper-http1",
+    feature = "hyper-http2",
+    feature = "hyper-client-legacy"
+))]
 mod client;
+pub(crate) mod policy;
+
+pub use policy::{PropagationPolicy, set_global_propagation_policy};
 
 #[cfg(feature = "reqwest")]
 pub mod reqwest;
@@ -12,5 +21,13 @@ pub mod reqwest;
 ))]
 pub mod hyper;
 
-#[cfg(test)]
+#[cfg(all(
+    test,
+    any(
+        feature = "reqwest",
+        feature = "hyper-http1",
+        feature = "hyper-http2",
+        feature = "hyper-client-legacy"
+    )
+))]
 mod test_utils;
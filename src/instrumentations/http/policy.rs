@@ -0,0 +1,349 @@
+use http::Uri;
+use std::sync::{Arc, RwLock};
+
+static GLOBAL_POLICY: RwLock<Option<Arc<PropagationPolicy>>> = RwLock::new(None);
+
+/// Policy deciding which destinations of outbound HTTP requests receive trace context.
+///
+/// Instrumented clients always record a client span, but inject propagation headers
+/// (e.g. `traceparent`, `baggage` and the [`RequestId`](crate::http::RequestId) header)
+/// only if the destination is trusted.
+/// A destination is trusted if it matches none of the deny rules and, if any allow rule
+/// is configured, at least one of the allow rules.
+///
+/// Rules match destinations by:
+/// - host: exact host name, or any subdomain with a `*.` prefix (e.g. `*.example.com`),
+///   compared case-insensitively
+/// - scheme: e.g. `https`, compared case-insensitively
+/// - URL prefix: e.g. `https://api.example.com/v1/`, matching destinations with the same
+///   scheme, host and port, and a path in the prefix path (`/v1` and `/v1/orders`,
+///   but not `/v1beta`). Scheme and host are compared case-insensitively
+///
+/// If the part of the destination a rule checks is unknown, e.g. the scheme of a request
+/// with a relative URI, deny rules match and allow rules don't, so that context is
+/// not propagated.
+///
+/// The policy is set per instrumented client, or globally with
+/// [`set_global_propagation_policy`]. Without any policy, all destinations are trusted.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::instrumentations::http::{
+///     PropagationPolicy, set_global_propagation_policy,
+/// };
+///
+/// let policy = PropagationPolicy::new()
+///     .allow_host("*.internal.example.com")
+///     .allow_url_prefix("https://partner.example.org/traced/")
+///     .deny_scheme("http");
+///
+/// set_global_propagation_policy(policy);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PropagationPolicy {
+    allow: Vec<DestinationRule>,
+    deny: Vec<DestinationRule>,
+}
+
+#[derive(Debug, Clone)]
+enum DestinationRule {
+    Host(String),
+    Scheme(String),
+    UrlPrefix(UrlPrefix),
+}
+
+#[derive(Debug, Clone)]
+struct UrlPrefix {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    /// Path without trailing slashes, empty for the root path.
+    path: String,
+}
+
+impl UrlPrefix {
+    /// Parses a URL prefix of a rule, panicking if it is not an absolute URL.
+    fn new(prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        Self::parse(&prefix).unwrap_or_else(|| {
+            panic!("invalid URL prefix {prefix:?}, expected an absolute URL")
+        })
+    }
+
+    fn parse(prefix: &str) -> Option<Self> {
+        let uri = prefix.trim().parse::<Uri>().ok()?;
+        let scheme = uri.scheme_str()?;
+        let host = uri.host()?;
+        if uri.authority()?.as_str().contains('@') {
+            return None;
+        }
+        Some(Self {
+            scheme: scheme.to_owned(),
+            host: host.trim_end_matches('.').to_owned(),
+            port: uri.port_u16().or_else(|| default_port(scheme)),
+            path: uri.path().trim_end_matches('/').to_owned(),
+        })
+    }
+
+    fn matches(&self, scheme: &str, host: &str, port: Option<u16>, path: &str) -> bool {
+        scheme.eq_ignore_ascii_case(&self.scheme)
+            && host.trim_end_matches('.').eq_ignore_ascii_case(&self.host)
+            && port.or_else(|| default_port(scheme)) == self.port
+            && path
+                .strip_prefix(&self.path)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    if scheme.eq_ignore_ascii_case("https") {
+        Some(443)
+    } else if scheme.eq_ignore_ascii_case("http") {
+        Some(80)
+    } else {
+        None
+    }
+}
+
+/// Destination of an outbound request as seen by a [`PropagationPolicy`].
+#[derive(Debug, Default)]
+pub(crate) struct Destination<'a> {
+    pub(crate) scheme: Option<&'a str>,
+    pub(crate) host: Option<&'a str>,
+    /// Explicit port, the default port of the scheme otherwise.
+    pub(crate) port: Option<u16>,
+    pub(crate) path: Option<&'a str>,
+}
+
+impl<'a> Destination<'a> {
+    /// Destination of a request to the given URI, unknown parts are `None`.
+    pub(crate) fn from_uri(uri: &'a Uri) -> Self {
+        Self {
+            scheme: uri.scheme_str(),
+            host: uri.host(),
+            port: uri.port_u16(),
+            path: Some(uri.path()),
+        }
+    }
+}
+
+impl DestinationRule {
+    /// Returns `None` if the destination lacks the part checked by the rule.
+    fn matches(&self, destination: &Destination<'_>) -> Option<bool> {
+        match self {
+            Self::Host(pattern) => destination.host.map(|host| {
+                let host = host.trim_end_matches('.');
+                match pattern.strip_prefix("*.") {
+                    Some(domain) => {
+                        let host = host.to_ascii_lowercase();
+                        host.ends_with(&format!(".{}", domain.to_ascii_lowercase()))
+                    }
+                    None => host.eq_ignore_ascii_case(pattern),
+                }
+            }),
+            Self::Scheme(scheme) => {
+                destination.scheme.map(|s| s.eq_ignore_ascii_case(scheme))
+            }
+            Self::UrlPrefix(prefix) => Some(prefix.matches(
+                destination.scheme?,
+                destination.host?,
+                destination.port,
+                destination.path?,
+            )),
+        }
+    }
+}
+
+impl PropagationPolicy {
+    /// Creates a policy trusting all destinations until rules are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts requests to the given host, or to any of its subdomains with a `*.` prefix.
+    pub fn allow_host(mut self, host: impl Into<String>) -> Self {
+        self.allow.push(DestinationRule::Host(host.into()));
+        self
+    }
+
+    /// Trusts requests using the given scheme.
+    pub fn allow_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.allow.push(DestinationRule::Scheme(scheme.into()));
+        self
+    }
+
+    /// Trusts requests to URLs in the given prefix, see [`PropagationPolicy`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is not an absolute URL, e.g. `https://api.example.com/v1`.
+    pub fn allow_url_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.allow
+            .push(DestinationRule::UrlPrefix(UrlPrefix::new(prefix)));
+        self
+    }
+
+    /// Never propagates context to the given host, or to any of its subdomains with
+    /// a `*.` prefix.
+    pub fn deny_host(mut self, host: impl Into<String>) -> Self {
+        self.deny.push(DestinationRule::Host(host.into()));
+        self
+    }
+
+    /// Never propagates context to requests using the given scheme.
+    pub fn deny_scheme(mut self, scheme: impl Into<String>) -> Self {
+        self.deny.push(DestinationRule::Scheme(scheme.into()));
+        self
+    }
+
+    /// Never propagates context to URLs in the given prefix, see [`PropagationPolicy`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix is not an absolute URL, e.g. `https://api.example.com/v1`.
+    pub fn deny_url_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.deny
+            .push(DestinationRule::UrlPrefix(UrlPrefix::new(prefix)));
+        self
+    }
+
+    pub(crate) fn is_trusted(&self, destination: &Destination<'_>) -> bool {
+        !self
+            .deny
+            .iter()
+            .any(|rule| rule.matches(destination).unwrap_or(true))
+            && (self.allow.is_empty()
+                || self
+                    .allow
+                    .iter()
+                    .any(|rule| rule.matches(destination).unwrap_or(false)))
+    }
+}
+
+/// Sets the [`PropagationPolicy`] used by instrumented clients without their own policy.
+pub fn set_global_propagation_policy(policy: PropagationPolicy) {
+    if let Ok(mut global) = GLOBAL_POLICY.write() {
+        *global = Some(Arc::new(policy));
+    }
+}
+
+/// Removes the global [`PropagationPolicy`], trusting all destinations again.
+#[cfg(all(test, feature = "tonic"))]
+pub(crate) fn clear_global_propagation_policy() {
+    if let Ok(mut global) = GLOBAL_POLICY.write() {
+        *global = None;
+    }
+}
+
+/// Returns `true` if trace context should be injected into a request to the destination,
+/// according to the client policy, or the global policy if the client has none.
+pub(crate) fn should_propagate(
+    policy: Option<&PropagationPolicy>,
+    destination: &Destination<'_>,
+) -> bool {
+    match policy {
+        Some(policy) => policy.is_trusted(destination),
+        None => match GLOBAL_POLICY.read() {
+            Ok(global) => global
+                .as_ref()
+                .is_none_or(|policy| policy.is_trusted(destination)),
+            Err(_) => true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+
+    fn is_trusted(policy: &PropagationPolicy, url: &str) -> bool {
+        let uri = url.parse::<Uri>().unwrap();
+        policy.is_trusted(&Destination::from_uri(&uri))
+    }
+
+    #[rstest]
+    #[case("https://api.internal.example.com/v1", true)]
+    #[case("https://API.Internal.Example.com/v1", true)]
+    #[case("https://internal.example.com/v1", false)]
+    #[case("https://evil-internal.example.com/v1", false)]
+    #[case("http://api.internal.example.com/v1", false)]
+    #[case("https://partner.example.org/traced/orders", true)]
+    #[case("https://partner.example.org/traced", true)]
+    #[case("HTTPS://Partner.Example.org:443/traced/orders", true)]
+    #[case("https://partner.example.org/tracedx", false)]
+    #[case("https://partner.example.org:8443/traced/orders", false)]
+    #[case("https://partner.example.org.evil.com/traced/orders", false)]
+    #[case("https://partner.example.org@evil.com/traced/orders", false)]
+    #[case("https://partner.example.org/other", false)]
+    #[case("https://auth.example.com/token", true)]
+    #[case("https://thirdparty.com/", false)]
+    fn test_propagation_policy(#[case] url: &str, #[case] expected: bool) {
+        let policy = PropagationPolicy::new()
+            .allow_host("*.internal.example.com")
+            .allow_host("auth.example.com")
+            .allow_url_prefix("https://partner.example.org/traced/")
+            .deny_scheme("http");
+
+        assert!(is_trusted(&policy, url) == expected);
+    }
+
+    #[rstest]
+    #[case("https://partner.example.org", true)]
+    #[case("https://partner.example.org/orders", true)]
+    #[case("https://partner.example.org.evil.com/", false)]
+    #[case("https://partner.example.org@evil.com/", false)]
+    #[case("http://partner.example.org/", false)]
+    fn test_url_prefix_without_path(#[case] url: &str, #[case] expected: bool) {
+        let policy =
+            PropagationPolicy::new().allow_url_prefix("https://partner.example.org");
+
+        assert!(is_trusted(&policy, url) == expected);
+    }
+
+    #[test]
+    fn test_deny_only_policy() {
+        let policy = PropagationPolicy::new().deny_host("*.thirdparty.com");
+
+        assert!(is_trusted(&policy, "https://api.example.com/"));
+        assert!(!is_trusted(&policy, "https://api.thirdparty.com/"));
+        assert!(!is_trusted(&policy, "/relative"));
+        assert!(PropagationPolicy::new().is_trusted(&Destination::default()));
+    }
+
+    #[test]
+    fn test_unknown_destination_parts() {
+        let destination = Destination {
+            host: Some("api.example.com"),
+            path: Some("/"),
+            ..Default::default()
+        };
+
+        let deny_scheme = PropagationPolicy::new().deny_scheme("http");
+        let allow_host = PropagationPolicy::new().allow_host("api.example.com");
+        let allow_prefix =
+            PropagationPolicy::new().allow_url_prefix("https://api.example.com");
+
+        assert!(!deny_scheme.is_trusted(&destination));
+        assert!(allow_host.is_trusted(&destination));
+        assert!(!allow_prefix.is_trusted(&destination));
+    }
+
+    #[rstest]
+    #[case("api.example.com/x")]
+    #[case("/x")]
+    #[case("https://user@api.example.com/")]
+    #[case("not a url")]
+    fn test_invalid_url_prefix(#[case] prefix: &str, #[values(false, true)] deny: bool) {
+        let result = std::panic::catch_unwind(|| match deny {
+            true => PropagationPolicy::new().deny_url_prefix(prefix),
+            false => PropagationPolicy::new().allow_url_prefix(prefix),
+        });
+
+        let message = result.unwrap_err();
+        let message = message.downcast_ref::<String>().unwrap();
+        assert!(message.contains(&format!("{prefix:?}")));
+    }
+}
@@ -17,14 +17,16 @@
 //! ```
 
 use futures_util::{FutureExt, future};
-use std::future::Future;
+use std::{future::Future, sync::Arc};
 
 use crate::{
     Context, Value,
     future::InstrumentedFuture,
-    http,
-    instrumentations::http::client::{
-        HttpClientSpanBuilder, HttpError, HttpResponse, UrlParts,
+    http::{self, HeaderCapture},
+    instrumentations::http::{
+        PropagationPolicy,
+        client::{HttpClientSpanBuilder, HttpError, HttpResponse, UrlParts},
+        policy::{Destination, should_propagate},
     },
 };
 
@@ -66,6 +68,10 @@ impl HttpResponse for reqwest::Response {
         self.version()
     }
 
+    fn headers(&self) -> &::http::HeaderMap {
+        self.headers()
+    }
+
     fn remote_addr(&self) -> Option<std::net::SocketAddr> {
         self.remote_addr()
     }
@@ -133,6 +139,8 @@ impl From<&reqwest::Request> for HttpClientSpanBuilder {
 pub struct InstrumentedRequestBuilder {
     inner: reqwest::RequestBuilder,
     context: Option<Context>,
+    propagation_policy: Option<Arc<PropagationPolicy>>,
+    header_capture: Option<HeaderCapture>,
 }
 
 impl InstrumentedRequestBuilder {
@@ -141,6 +149,8 @@ impl InstrumentedRequestBuilder {
         Self {
             inner,
             context: None,
+            propagation_policy: None,
+            header_capture: None,
         }
     }
 
@@ -156,6 +166,24 @@ impl InstrumentedRequestBuilder {
         self
     }
 
+    /// Sets the [`PropagationPolicy`] deciding whether trace context is injected into
+    /// this request, overriding the global policy.
+    pub fn propagation_policy(
+        mut self,
+        policy: impl Into<Arc<PropagationPolicy>>,
+    ) -> Self {
+        self.propagation_policy = Some(policy.into());
+        self
+    }
+
+    /// Sets the [`HeaderCapture`] deciding which headers are recorded on client spans
+    /// of this request, overriding the `OTEL_INSTRUMENTATION_HTTP_CLIENT_CAPTURE_HEADERS_*`
+    /// environment variables.
+    pub fn capture_headers(mut self, header_capture: HeaderCapture) -> Self {
+        self.header_capture = Some(header_capture);
+        self
+    }
+
     /// Sends the request and records an outbound HTTP client span around it.
     pub fn send(self) -> impl Future<Output = Result<reqwest::Response, reqwest::Error>> {
         let (client, request_result) = self.inner.build_split();
@@ -164,9 +192,21 @@ impl InstrumentedRequestBuilder {
             Ok(req) => req,
             Err(err) => return future::err(err).left_future(),
         };
-        let span = HttpClientSpanBuilder::from(&request).start(&self.context);
-
-        http::inject_context_on_context(span.context(), request.headers_mut());
+        let span = HttpClientSpanBuilder::from(&request)
+            .capture_headers(self.header_capture.as_ref(), request.headers())
+            .start(&self.context);
+
+        let url = request.url();
+        let destination = Destination {
+            scheme: Some(url.scheme()),
+            host: url.host_str(),
+            port: url.port(),
+            path: Some(url.path()),
+        };
+        if should_propagate(self.propagation_policy.as_deref(), &destination) {
+            http::inject_context_on_context(span.context(), request.headers_mut());
+            http::inject_request_id(span.context(), request.headers_mut());
+        }
 
         let future = client.execute(request);
         InstrumentedFuture::new(future, span).right_future()
@@ -177,7 +217,10 @@ impl InstrumentedRequestBuilder {
 mod tests {
     use super::ReqwestBuilderInstrument;
     use crate::{
-        Context, OpenTelemetryLayer, instrumentations::http::test_utils::*, semconv,
+        Context, OpenTelemetryLayer,
+        http::HeaderCapture,
+        instrumentations::http::{PropagationPolicy, test_utils::*},
+        semconv,
     };
     use assert2::assert;
     use axum::http::StatusCode;
@@ -271,6 +314,86 @@ mod tests {
         assert!(span_id == client_span.span_context.span_id().to_string());
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn propagates_request_id() {
+        let _telemetry = configure_test_tracing();
+        let server = spawn_server().await;
+        let context = request_id_context("abc");
+
+        test_client()
+            .get(format!("{}/ok", server.base_url))
+            .instrument()
+            .context(&context)
+            .send()
+            .await
+            .unwrap();
+
+        assert!(server.state.request_id_for("/ok") == Some("abc".to_owned()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn skips_propagation_to_untrusted_destinations() {
+        let telemetry = configure_test_tracing();
+        let server = spawn_server().await;
+        let policy = PropagationPolicy::new().deny_host("127.0.0.1");
+
+        test_client()
+            .get(format!("{}/ok", server.base_url))
+            .instrument()
+            .propagation_policy(policy)
+            .send()
+            .await
+            .unwrap();
+
+        let spans = force_flush_and_get_spans(&telemetry);
+        let span = find_span(&spans, "GET");
+
+        assert!(span.span_kind == SpanKind::Client);
+        assert!(server.state.traceparent_for("/ok").is_none());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn captures_allowed_headers() {
+        let telemetry = configure_test_tracing();
+        let server = spawn_server().await;
+        let header_capture = HeaderCapture::new()
+            .request_headers(["x-request-id", "authorization"])
+            .response_headers(["content-length"]);
+
+        test_client()
+            .get(format!("{}/ok", server.base_url))
+            .header("x-request-id", "abc")
+            .header("authorization", "Bearer secret")
+            .instrument()
+            .capture_headers(header_capture)
+            .send()
+            .await
+            .unwrap();
+
+        let spans = force_flush_and_get_spans(&telemetry);
+        let span = find_span(&spans, "GET");
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.to_string())
+        };
+
+        assert!(
+            attribute("http.request.header.x-request-id") == Some(r#"["abc"]"#.into())
+        );
+        assert!(
+            attribute("http.request.header.authorization")
+                == Some(r#"["REDACTED"]"#.into())
+        );
+        assert!(
+            attribute("http.response.header.content-length") == Some(r#"["0"]"#.into())
+        );
+    }
+
     #[tokio::test]
     #[serial]
     async fn marks_client_error_responses_as_errors() {
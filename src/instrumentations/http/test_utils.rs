@@ -17,6 +17,7 @@ use tokio::{net::TcpListener, task::JoinHandle};
 #[derive(Clone, Default)]
 pub struct TestState {
     traceparents: Arc<Mutex<Vec<(String, String)>>>,
+    request_ids: Arc<Mutex<Vec<(String, String)>>>,
 }
 
 impl TestState {
@@ -30,6 +31,15 @@ impl TestState {
                 .unwrap()
                 .push((path.to_owned(), traceparent.to_owned()));
         }
+        if let Some(request_id) = headers
+            .get("x-request-id")
+            .and_then(|value| value.to_str().ok())
+        {
+            self.request_ids
+                .lock()
+                .unwrap()
+                .push((path.to_owned(), request_id.to_owned()));
+        }
     }
 
     pub fn traceparent_for(&self, path: &str) -> Option<String> {
@@ -41,6 +51,24 @@ impl TestState {
             .find(|(recorded_path, _)| recorded_path == path)
             .map(|(_, traceparent)| traceparent.clone())
     }
+
+    pub fn request_id_for(&self, path: &str) -> Option<String> {
+        self.request_ids
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|(recorded_path, _)| recorded_path == path)
+            .map(|(_, request_id)| request_id.clone())
+    }
+}
+
+pub fn request_id_context(request_id: &str) -> crate::Context {
+    let cell = crate::http::RequestIdCell::default();
+    cell.set(
+        crate::http::RequestId::new("x-request-id".parse().unwrap(), request_id).unwrap(),
+    );
+    crate::Context::new().with_value(cell)
 }
 
 pub struct TestServer {
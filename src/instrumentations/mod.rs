@@ -4,6 +4,7 @@
     feature = "reqwest",
     feature = "hyper-http1",
     feature = "hyper-http2",
-    feature = "hyper-client-legacy"
+    feature = "hyper-client-legacy",
+    feature = "tonic"
 ))]
 pub mod http;
@@ -11,11 +11,14 @@
 //!
 //! - OpenTelemetry tracing instrumentation
 //! - Formatted logs with tracing metadata
+//! - Per-target log and span filtering, reloadable at runtime
 //! - Context Propagation for incoming and outgoing HTTP requests
-//! - Axum middleware to instrument http services
+//! - Baggage helpers with W3C size limits
+//! - Axum and generic tower middleware to instrument http services
 //! - Hyper connection instrumentation for outbound HTTP requests
 //! - Legacy hyper client instrumentation for outbound HTTP requests
 //! - Reqwest instrumentation for outbound HTTP requests
+//! - Tonic gRPC server and client instrumentation
 //! - AWS Lambda instrumentation layer
 //! - AWS SDK instrumentation with automatic attribute extraction
 //! - Integration testing tools
@@ -24,15 +27,19 @@
 //!
 //! ## Core Features
 //! - `axum`: Axum web framework middleware support
+//! - `axum-ws`: WebSocket connection instrumentation for Axum
+//! - `http-server`: Generic HTTP server middleware for any `tower` service
 //! - `hyper`: Hyper connection instrumentation for outbound HTTP clients
 //! - `hyper-http1`: Hyper HTTP/1 connection instrumentation
 //! - `hyper-http2`: Hyper HTTP/2 connection instrumentation
 //! - `hyper-client-legacy`: Hyper-util legacy client instrumentation
 //! - `reqwest`: Reqwest instrumentation for outbound HTTP clients
+//! - `tonic`: gRPC metadata context propagation and tonic server and client middleware
 //! - `rustls`: Enables rustls TLS backend for HTTP exporters
 //! - `test`: Testing utilities for OpenTelemetry validation
 //! - `zipkin`: Zipkin context propagation support (enabled by default)
 //! - `xray`: AWS X-Ray context propagation support
+//! - `datadog`: Datadog context propagation support
 //! - `future`: Future instrumentation utilities (mostly used internally)
 //!
 //! ## AWS Features
@@ -93,6 +100,8 @@ pub use opentelemetry_sdk::{
 pub use opentelemetry_semantic_conventions::attribute as semconv;
 pub use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
 
+pub mod baggage;
+pub mod filter;
 pub mod fmt;
 pub mod http;
 pub mod instrumentations;
@@ -100,7 +109,7 @@ pub mod middleware;
 pub mod otlp;
 pub mod propagation;
 
-#[cfg(feature = "axum")]
+#[cfg(feature = "http-server")]
 pub use tracing_opentelemetry_instrumentation_sdk;
 
 #[cfg(feature = "test")]
@@ -109,7 +118,9 @@ pub mod test;
 #[cfg(feature = "future")]
 pub mod future;
 
-mod filter;
+#[cfg(any(feature = "http-server", feature = "future"))]
+pub mod panic;
+
 mod util;
 
 /// Resource detection utility for automatically configuring OpenTelemetry service metadata.
@@ -203,9 +214,18 @@ macro_rules! fmt_layer {
         #[cfg(debug_assertions)]
         let layer = layer.compact().with_span_events(FmtSpan::CLOSE);
         #[cfg(not(debug_assertions))]
-        let layer = layer.json().event_format(fmt::JsonFormat);
+        let layer = tracing_subscriber::Layer::and_then(
+            fmt::SpanTimingLayer,
+            layer
+                .json()
+                .with_span_events(fmt::read_span_events_from_env())
+                .event_format(fmt::JsonFormat)
+                .with_writer(std::io::stdout),
+        );
+        #[cfg(debug_assertions)]
+        let layer = layer.with_writer(std::io::stdout);
 
-        layer.with_writer(std::io::stdout)
+        layer
     }};
 }
 
@@ -252,6 +272,73 @@ pub fn init_tracing_with_fallbacks(
     fallback_service_name: &'static str,
     fallback_service_version: &'static str,
 ) -> TracerProvider {
+    let (tracer_provider, ()) = init_tracing_with_filter(
+        log_level,
+        fallback_service_name,
+        fallback_service_version,
+        |filter| (filter, ()),
+    );
+    tracer_provider
+}
+
+/// Initializes tracing like [`init_tracing_with_fallbacks`] and returns a handle to
+/// change the log and span filter at runtime.
+///
+/// # Arguments
+///
+/// - `log_level`: The initial minimum log level for events
+/// - `fallback_service_name`: Default service name if not found in environment variables
+/// - `fallback_service_version`: Default service version if not found in environment variables
+///
+/// # Returns
+///
+/// A configured [`TracerProvider`] that should be kept alive for the duration of the application
+/// and passed to [`shutdown_tracer_provider`] on shutdown, and a [`filter::FilterHandle`]
+/// to change the event level, span level and per-target directives.
+///
+/// # Examples
+///
+/// ```rust
+/// use telemetry_rust::{init_reloadable_tracing_with_fallbacks, shutdown_tracer_provider};
+/// use tracing::Level;
+///
+/// let (tracer_provider, filter_handle) =
+///     init_reloadable_tracing_with_fallbacks(Level::INFO, "my-service", "1.0.0");
+///
+/// // Raise verbosity without a redeploy
+/// filter_handle.set_log_level(Level::DEBUG)?;
+///
+/// shutdown_tracer_provider(&tracer_provider);
+/// # Ok::<(), telemetry_rust::filter::ReloadError>(())
+/// ```
+///
+/// # Panics
+///
+/// This function will panic if:
+/// - The OTLP tracer provider cannot be initialized
+/// - The text map propagator cannot be configured
+pub fn init_reloadable_tracing_with_fallbacks(
+    log_level: tracing::Level,
+    fallback_service_name: &'static str,
+    fallback_service_version: &'static str,
+) -> (TracerProvider, filter::FilterHandle) {
+    init_tracing_with_filter(
+        log_level,
+        fallback_service_name,
+        fallback_service_version,
+        filter::FilterHandle::new,
+    )
+}
+
+fn init_tracing_with_filter<L, T>(
+    log_level: tracing::Level,
+    fallback_service_name: &'static str,
+    fallback_service_version: &'static str,
+    make_filter_layer: impl FnOnce(filter::TracingFilter) -> (L, T),
+) -> (TracerProvider, T)
+where
+    L: tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync + 'static,
+{
     // set to debug to log detected resources, configuration read and infered
     let setup_subscriber = tracing_subscriber::registry()
         .with(Into::<LevelFilter>::into(log_level))
@@ -269,15 +356,22 @@ pub fn init_tracing_with_fallbacks(
         propagation::TextMapSplitPropagator::from_env().expect("TextMapPropagator setup"),
     );
 
+    let (filter, invalid_directives) = filter::TracingFilter::from_env(log_level);
+    let (filter_layer, output) = make_filter_layer(filter);
     let otel_layer =
         OpenTelemetryLayer::new(tracer_provider.tracer(env!("CARGO_PKG_NAME")));
     let subscriber = tracing_subscriber::registry()
-        .with(Into::<filter::TracingFilter>::into(log_level))
+        .with(filter_layer)
         .with(fmt_layer!())
         .with(otel_layer);
     tracing::subscriber::set_global_default(subscriber).unwrap();
 
-    tracer_provider
+    // reported once the filter is installed, the setup subscriber may not log warnings
+    for filter::InvalidDirectives { key, value, error } in invalid_directives {
+        tracing::warn!(target: "otel::setup", %error, "invalid {key} directives: {value:?}");
+    }
+
+    (tracer_provider, output)
 }
 
 /// Convenience macro for initializing tracing with package name and version as fallbacks.
@@ -316,6 +410,42 @@ macro_rules! init_tracing {
     };
 }
 
+/// Convenience macro for initializing reloadable tracing with package name and version as fallbacks.
+///
+/// This macro calls [`init_reloadable_tracing_with_fallbacks`] using the current package's name
+/// and version from `CARGO_PKG_NAME` and `CARGO_PKG_VERSION` environment variables as fallback values.
+///
+/// # Arguments
+///
+/// - `log_level`: The initial minimum log level for events (e.g., `Level::INFO`)
+///
+/// # Returns
+///
+/// A configured [`TracerProvider`] and a [`filter::FilterHandle`] to change the filter at runtime.
+///
+/// # Examples
+///
+/// ```rust
+/// use telemetry_rust::{init_reloadable_tracing, shutdown_tracer_provider};
+/// use tracing::Level;
+///
+/// let (tracer_provider, filter_handle) = init_reloadable_tracing!(Level::INFO);
+///
+/// // Your application code here...
+///
+/// shutdown_tracer_provider(&tracer_provider);
+/// ```
+#[macro_export]
+macro_rules! init_reloadable_tracing {
+    ($log_level:expr) => {
+        $crate::init_reloadable_tracing_with_fallbacks(
+            $log_level,
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        )
+    };
+}
+
 /// Properly shuts down a tracer provider, flushing pending spans and cleaning up resources.
 ///
 /// This function performs a graceful shutdown of the tracer provider by:
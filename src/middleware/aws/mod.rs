@@ -22,9 +22,8 @@ use opentelemetry::{
     trace::{Span as _, SpanBuilder, SpanKind, Status, Tracer},
 };
 use std::error::Error;
-use tracing::Span;
 
-use crate::{Context, KeyValue, OpenTelemetrySpanExt, semconv};
+use crate::{Context, KeyValue, semconv};
 
 mod instrumentation;
 mod operations;
@@ -393,7 +392,7 @@ impl<'a> AwsSpanBuilder<'a> {
     pub fn start(self) -> AwsSpan {
         match self.context {
             Some(context) => self.start_with_context(context),
-            None => self.start_with_context(&Span::current().context()),
+            None => self.start_with_context(&crate::baggage::current_context()),
         }
     }
 }
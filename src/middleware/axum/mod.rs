@@ -0,0 +1,892 @@
+//! Axum web framework middleware.
+//!
+//! Provides middleware for the Axum web framework to automatically
+//! instrument HTTP requests with OpenTelemetry tracing.
+//!
+//! [`OtelAxumLayer`] is a thin wrapper over the generic [`OtelHttpServerLayer`], using
+//! the matched path of axum as the route of requests.
+
+use http::Request;
+use opentelemetry::metrics::Meter;
+use std::fmt;
+use tower::Layer;
+
+use super::http_server::OtelHttpServerLayer;
+use crate::{filter::DebugTrigger, http::HeaderCapture};
+
+#[cfg(feature = "axum-ws")]
+pub mod ws;
+
+pub use super::http_server::{
+    ContextTrust, IpNet, OnRequest, OnResponse, PeerAddress, RequestFilter,
+    RequestIdGenerator, RequestIdHeader, ResponseBody, ResponseFuture, RouteExtractor,
+    TraceResponseHeader, TrustedProxies,
+};
+
+/// Function type for extracting string representation from a matched path type.
+///
+/// Used to convert Axum's matched path type to a string for span attributes.
+pub type AsStr<T> = fn(&T) -> &str;
+
+/// OpenTelemetry service wrapper for Axum applications.
+///
+/// This service wraps Axum services to provide automatic HTTP request tracing
+/// with OpenTelemetry spans and context propagation.
+pub type OtelAxumService<S, P, F = (), OnReq = (), OnRes = ()> =
+    super::http_server::OtelHttpServerService<S, MatchedPathRoute<P>, F, OnReq, OnRes>;
+
+/// [`RouteExtractor`] reading the route from the matched path in request extensions.
+pub struct MatchedPathRoute<P>(AsStr<P>);
+
+impl<P> Clone for MatchedPathRoute<P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P> Copy for MatchedPathRoute<P> {}
+
+impl<P> fmt::Debug for MatchedPathRoute<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MatchedPathRoute").field(&self.0).finish()
+    }
+}
+
+impl<B, P> RouteExtractor<B> for MatchedPathRoute<P>
+where
+    P: Send + Sync + 'static,
+{
+    fn route(&self, request: &Request<B>) -> Option<String> {
+        let matched_path = request.extensions().get::<P>()?;
+        Some((self.0)(matched_path).to_owned())
+    }
+}
+
+/// OpenTelemetry layer for Axum applications.
+///
+/// This layer provides automatic tracing instrumentation for Axum web applications,
+/// creating spans for HTTP requests with appropriate semantic attributes.
+///
+/// The layer is generic over [`axum::extract::MatchedPath`](https://docs.rs/axum/latest/axum/extract/struct.MatchedPath.html),
+/// making it compatible with different versions of axum without being tied to any specific one.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{Router, routing};
+/// use telemetry_rust::middleware::axum::OtelAxumLayer;
+///
+/// let app: Router = axum::Router::new()
+///     .nest("/api", Router::new()) // api_routes would be your actual routes
+///     .layer(OtelAxumLayer::new(axum::extract::MatchedPath::as_str));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OtelAxumLayer<P, F = (), OnReq = (), OnRes = ()> {
+    inner: OtelHttpServerLayer<MatchedPathRoute<P>, F, OnReq, OnRes>,
+}
+
+// add a builder like api
+impl<P> OtelAxumLayer<P> {
+    /// Creates a new OpenTelemetry layer for Axum.
+    ///
+    /// # Arguments
+    ///
+    /// * `matched_path_as_str` - [`axum::extract::MatchedPath::as_str`] or any function to convert [`axum::extract::MatchedPath`] to a `&str`
+    ///
+    ///  [`axum::extract::MatchedPath::as_str`]: https://docs.rs/axum/latest/axum/extract/struct.MatchedPath.html#method.as_str
+    ///  [`axum::extract::MatchedPath`]: https://docs.rs/axum/latest/axum/extract/struct.MatchedPath.html
+    pub fn new(matched_path_as_str: AsStr<P>) -> Self {
+        OtelAxumLayer {
+            inner: OtelHttpServerLayer::new()
+                .route(MatchedPathRoute(matched_path_as_str)),
+        }
+    }
+}
+
+impl<P, F, OnReq, OnRes> OtelAxumLayer<P, F, OnReq, OnRes> {
+    /// Sets a predicate to selectively trace requests.
+    ///
+    /// Requests which are not traced get neither a span nor metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Predicate that returns true for requests that should be traced,
+    ///   see [`RequestFilter`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{Router, body::Body, extract::MatchedPath, http::{Method, Request}};
+    /// use telemetry_rust::middleware::axum::OtelAxumLayer;
+    ///
+    /// let layer = OtelAxumLayer::new(MatchedPath::as_str).filter(|req: &Request<Body>| {
+    ///     req.method() != Method::OPTIONS && req.uri().path() != "/health"
+    /// });
+    /// let app: Router = Router::new().layer(layer);
+    /// ```
+    pub fn filter<F2>(self, filter: F2) -> OtelAxumLayer<P, F2, OnReq, OnRes> {
+        OtelAxumLayer {
+            inner: self.inner.filter(filter),
+        }
+    }
+
+    /// Sets a hook to customize the server span of each traced request, e.g. to add
+    /// attributes or rename the span.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_request` - Hook called with the request and its span, see [`OnRequest`]
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Body, extract::MatchedPath, http::Request};
+    /// use telemetry_rust::{OpenTelemetrySpanExt, middleware::axum::OtelAxumLayer};
+    /// use tracing::Span;
+    ///
+    /// let layer = OtelAxumLayer::new(MatchedPath::as_str).on_request(
+    ///     |req: &Request<Body>, span: &Span| {
+    ///         if let Some(tenant) = req.headers().get("x-tenant-id") {
+    ///             let tenant = String::from_utf8_lossy(tenant.as_bytes()).into_owned();
+    ///             span.set_attribute("app.tenant", tenant);
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn on_request<OnReq2>(
+        self,
+        on_request: OnReq2,
+    ) -> OtelAxumLayer<P, F, OnReq2, OnRes> {
+        OtelAxumLayer {
+            inner: self.inner.on_request(on_request),
+        }
+    }
+
+    /// Sets a hook to customize the server span of each traced request with its
+    /// response.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_response` - Hook called with the response and the span, see [`OnResponse`]
+    pub fn on_response<OnRes2>(
+        self,
+        on_response: OnRes2,
+    ) -> OtelAxumLayer<P, F, OnReq, OnRes2> {
+        OtelAxumLayer {
+            inner: self.inner.on_response(on_response),
+        }
+    }
+
+    /// Configures whether to inject OpenTelemetry context into responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `inject_context` - Whether to inject trace context into response headers
+    pub fn inject_context(self, inject_context: bool) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.inject_context(inject_context),
+        }
+    }
+
+    /// Adds a response header reporting the trace of the request to the client.
+    ///
+    /// See [`OtelHttpServerLayer::trace_response_header`].
+    pub fn trace_response_header(self, header: TraceResponseHeader) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.trace_response_header(header),
+        }
+    }
+
+    /// Enables request ids, read from the inbound header or generated.
+    ///
+    /// See [`OtelHttpServerLayer::request_id`].
+    pub fn request_id(self, request_id: RequestIdHeader) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.request_id(request_id),
+        }
+    }
+
+    /// Enables per-request debug logging for requests matching the trigger.
+    ///
+    /// See [`OtelHttpServerLayer::debug_trigger`].
+    pub fn debug_trigger(self, debug_trigger: DebugTrigger) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.debug_trigger(debug_trigger),
+        }
+    }
+
+    /// Sets the policy deciding whether the trace context of incoming requests is trusted.
+    ///
+    /// See [`OtelHttpServerLayer::context_trust`].
+    pub fn context_trust(self, context_trust: ContextTrust) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.context_trust(context_trust),
+        }
+    }
+
+    /// Sets the function to read the peer address of a connection from request extensions.
+    ///
+    /// See [`OtelHttpServerLayer::peer_address`].
+    pub fn peer_address(self, peer_address: PeerAddress) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.peer_address(peer_address),
+        }
+    }
+
+    /// Sets the proxies trusted to report the client address in proxy headers.
+    ///
+    /// See [`OtelHttpServerLayer::trusted_proxies`].
+    pub fn trusted_proxies(self, trusted_proxies: TrustedProxies) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.trusted_proxies(trusted_proxies),
+        }
+    }
+
+    /// Sets the request and response headers recorded as span attributes.
+    ///
+    /// See [`OtelHttpServerLayer::capture_headers`].
+    pub fn capture_headers(self, header_capture: HeaderCapture) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.capture_headers(header_capture),
+        }
+    }
+
+    /// Sets the meter recording the HTTP server metrics.
+    ///
+    /// See [`OtelHttpServerLayer::meter`].
+    pub fn meter(self, meter: &Meter) -> Self {
+        OtelAxumLayer {
+            inner: self.inner.meter(meter),
+        }
+    }
+}
+
+impl<S, P, F, OnReq, OnRes> Layer<S> for OtelAxumLayer<P, F, OnReq, OnRes>
+where
+    F: Clone,
+    OnReq: Clone,
+    OnRes: Clone,
+{
+    /// The wrapped service
+    type Service = OtelAxumService<S, P, F, OnReq, OnRes>;
+    fn layer(&self, inner: S) -> Self::Service {
+        self.inner.layer(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semconv;
+    use assert2::assert;
+    use axum::extract::MatchedPath;
+    use http::Response;
+    use opentelemetry::{
+        global,
+        propagation::TextMapPropagator,
+        trace::{SpanId, TraceId, TracerProvider as _},
+    };
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator,
+        trace::{InMemorySpanExporter, SdkTracerProvider, SpanData},
+    };
+    use rstest::rstest;
+    use serial_test::serial;
+    use std::{convert::Infallible, net::SocketAddr};
+    use tower::ServiceExt;
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const SPAN_ID: &str = "00f067aa0ba902b7";
+
+    async fn server_span(
+        layer: OtelAxumLayer<MatchedPath>,
+        req: Request<()>,
+    ) -> (SpanData, Response<ResponseBody<String>>) {
+        server_span_with_propagator(layer, req, TraceContextPropagator::new()).await
+    }
+
+    async fn server_span_with_propagator(
+        layer: OtelAxumLayer<MatchedPath>,
+        req: Request<()>,
+        propagator: impl TextMapPropagator + Send + Sync + 'static,
+    ) -> (SpanData, Response<ResponseBody<String>>) {
+        let (mut spans, response) = server_spans(layer, req, propagator).await;
+        assert!(spans.len() == 1);
+        (spans.remove(0), response)
+    }
+
+    async fn server_spans<F, OnReq, OnRes>(
+        layer: OtelAxumLayer<MatchedPath, F, OnReq, OnRes>,
+        req: Request<()>,
+        propagator: impl TextMapPropagator + Send + Sync + 'static,
+    ) -> (Vec<SpanData>, Response<ResponseBody<String>>)
+    where
+        F: RequestFilter<()> + Clone,
+        OnReq: OnRequest<()> + Clone,
+        OnRes: OnResponse<String> + Clone,
+    {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        global::set_text_map_propagator(propagator);
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let service = layer.layer(tower::service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(String::new()))
+        }));
+        let response = service.oneshot(req).await.unwrap();
+
+        provider.force_flush().unwrap();
+        (exporter.get_finished_spans().unwrap(), response)
+    }
+
+    #[rstest]
+    #[case(ContextTrust::always(), None, true)]
+    #[case(ContextTrust::never(), Some("10.0.0.1:1234"), false)]
+    #[case(ContextTrust::never().trust_network("10.0.0.0/8".parse().unwrap()), Some("10.0.0.1:1234"), true)]
+    #[case(ContextTrust::never().trust_network("10.0.0.0/8".parse().unwrap()), Some("192.168.0.1:1234"), false)]
+    #[tokio::test]
+    #[serial]
+    async fn test_context_trust(
+        #[case] trust: ContextTrust,
+        #[case] peer_address: Option<&str>,
+        #[case] trusted: bool,
+    ) {
+        let layer = OtelAxumLayer::new(MatchedPath::as_str)
+            .context_trust(trust)
+            .peer_address(|extensions| extensions.get::<SocketAddr>().copied());
+        let mut req = Request::builder()
+            .uri("/")
+            .header("traceparent", format!("00-{TRACE_ID}-{SPAN_ID}-01"))
+            .body(())
+            .unwrap();
+        if let Some(peer_address) = peer_address {
+            req.extensions_mut()
+                .insert(peer_address.parse::<SocketAddr>().unwrap());
+        }
+
+        let (span, _) = server_span(layer, req).await;
+        let trace_id = TraceId::from_hex(TRACE_ID).unwrap();
+        let span_id = SpanId::from_hex(SPAN_ID).unwrap();
+
+        if trusted {
+            assert!(span.span_context.trace_id() == trace_id);
+            assert!(span.parent_span_id == span_id);
+            assert!(span.links.is_empty());
+        } else {
+            assert!(span.span_context.trace_id() != trace_id);
+            assert!(span.parent_span_id == SpanId::INVALID);
+            assert!(span.links.len() == 1);
+            assert!(span.links[0].span_context.trace_id() == trace_id);
+            assert!(span.links[0].span_context.span_id() == span_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_matched_path_route() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = axum::Router::new()
+            .route("/users/{id}", axum::routing::get(|| async { "user" }))
+            .layer(OtelAxumLayer::new(MatchedPath::as_str));
+        let req = Request::builder()
+            .uri("/users/42")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        drop(app.oneshot(req).await.unwrap());
+
+        provider.force_flush().unwrap();
+        let spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        assert!(spans[0].name == "GET /users/{id}");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_trace_response_headers() {
+        let layer = OtelAxumLayer::new(MatchedPath::as_str)
+            .trace_response_header(TraceResponseHeader::TraceResponse)
+            .trace_response_header(TraceResponseHeader::TraceId(
+                http::HeaderName::from_static("x-trace-id"),
+            ));
+        let req = Request::builder()
+            .uri("/")
+            .header("traceparent", format!("00-{TRACE_ID}-{SPAN_ID}-01"))
+            .body(())
+            .unwrap();
+
+        let (span, response) = server_span(layer, req).await;
+        let span_id = span.span_context.span_id();
+
+        assert!(span_id != SpanId::from_hex(SPAN_ID).unwrap());
+        assert!(
+            response.headers()["traceresponse"]
+                == format!("00-{TRACE_ID}-{span_id}-01").as_str()
+        );
+        assert!(response.headers()["x-trace-id"] == TRACE_ID);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_extracted_by_attribute() {
+        let propagator = crate::propagation::TextMapSplitPropagator::builder()
+            .extract("tracecontext")
+            .extract("jaeger")
+            .diagnostics(true)
+            .build()
+            .unwrap();
+        let req = Request::builder()
+            .uri("/")
+            .header("uber-trace-id", format!("{TRACE_ID}:{SPAN_ID}:0:1"))
+            .body(())
+            .unwrap();
+
+        let layer = OtelAxumLayer::new(MatchedPath::as_str);
+        let (span, _) = server_span_with_propagator(layer, req, propagator).await;
+
+        assert!(span.parent_span_id == SpanId::from_hex(SPAN_ID).unwrap());
+        assert!(span.attributes.iter().any(|kv| {
+            kv.key.as_str() == "propagation.extracted_by" && kv.value.as_str() == "jaeger"
+        }));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_client_address_attributes() {
+        let layer = OtelAxumLayer::new(MatchedPath::as_str)
+            .peer_address(|extensions| extensions.get::<SocketAddr>().copied())
+            .trusted_proxies(TrustedProxies::count(1));
+        let mut req = Request::builder()
+            .uri("/")
+            .header("x-forwarded-for", "198.51.100.7:5000")
+            .body(())
+            .unwrap();
+        req.extensions_mut()
+            .insert("10.0.0.2:4000".parse::<SocketAddr>().unwrap());
+
+        let (span, _) = server_span(layer, req).await;
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+
+        assert!(attribute(semconv::CLIENT_ADDRESS) == Some("198.51.100.7".into()));
+        assert!(attribute(semconv::CLIENT_PORT) == Some(5000.into()));
+        assert!(attribute(semconv::NETWORK_PEER_ADDRESS) == Some("10.0.0.2".into()));
+        assert!(attribute(semconv::NETWORK_PEER_PORT) == Some(4000.into()));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_capture_headers() {
+        let layer = OtelAxumLayer::new(MatchedPath::as_str).capture_headers(
+            HeaderCapture::new().request_headers(["x-request-id", "cookie"]),
+        );
+        let req = Request::builder()
+            .uri("/")
+            .header("x-request-id", "abc")
+            .header("cookie", "session=secret")
+            .body(())
+            .unwrap();
+
+        let (span, _) = server_span(layer, req).await;
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.to_string())
+        };
+
+        assert!(
+            attribute("http.request.header.x-request-id") == Some(r#"["abc"]"#.into())
+        );
+        assert!(
+            attribute("http.request.header.cookie") == Some(r#"["REDACTED"]"#.into())
+        );
+    }
+
+    #[rstest]
+    #[case(http::Method::GET, "/", 1)]
+    #[case(http::Method::OPTIONS, "/", 0)]
+    #[case(http::Method::GET, "/health", 0)]
+    #[tokio::test]
+    #[serial]
+    async fn test_request_filter(
+        #[case] method: http::Method,
+        #[case] path: &str,
+        #[case] expected_spans: usize,
+    ) {
+        let health = "/health".to_owned();
+        let layer =
+            OtelAxumLayer::new(MatchedPath::as_str).filter(move |req: &Request<()>| {
+                req.method() != http::Method::OPTIONS && req.uri().path() != health
+            });
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(())
+            .unwrap();
+
+        let (spans, _) = server_spans(layer, req, TraceContextPropagator::new()).await;
+
+        assert!(spans.len() == expected_spans);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_request_and_response_hooks() {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let layer = OtelAxumLayer::new(MatchedPath::as_str)
+            .on_request(|req: &Request<()>, span: &Span| {
+                span.record("otel.name", format!("custom {}", req.uri().path()));
+                span.set_attribute("app.tenant", "acme");
+            })
+            .on_response(|res: &Response<String>, span: &Span| {
+                span.set_attribute(
+                    "app.status_class",
+                    i64::from(res.status().as_u16() / 100),
+                );
+            });
+        let req = Request::builder().uri("/orders").body(()).unwrap();
+
+        let (mut spans, _) =
+            server_spans(layer, req, TraceContextPropagator::new()).await;
+        let span = spans.remove(0);
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+
+        assert!(span.name == "custom /orders");
+        assert!(attribute("app.tenant") == Some("acme".into()));
+        assert!(attribute("app.status_class") == Some(2.into()));
+    }
+
+    /// Returns the number of spans ended before the response body is sent, and the
+    /// server span once the body is consumed or dropped.
+    async fn response_body_span<B>(body: B, consume: bool) -> (usize, SpanData)
+    where
+        B: http_body::Body<Data = bytes::Bytes> + Send + 'static,
+        B::Error: std::fmt::Display + std::fmt::Debug,
+    {
+        use http_body_util::BodyExt;
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let body = std::sync::Arc::new(std::sync::Mutex::new(Some(body)));
+        let service = OtelAxumLayer::new(MatchedPath::as_str).layer(tower::service_fn(
+            move |_: Request<()>| {
+                let response = Response::new(body.lock().unwrap().take().unwrap());
+                std::future::ready(Ok::<_, Infallible>(response))
+            },
+        ));
+        let response = service.oneshot(Request::new(())).await.unwrap();
+        provider.force_flush().unwrap();
+        let ended_before_body = exporter.get_finished_spans().unwrap().len();
+
+        if consume {
+            let _ = response.into_body().collect().await;
+        } else {
+            drop(response);
+        }
+        provider.force_flush().unwrap();
+        let mut spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        (ended_before_body, spans.remove(0))
+    }
+
+    fn span_attribute(span: &SpanData, key: &str) -> Option<opentelemetry::Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.clone())
+    }
+
+    #[tokio::test]
+    async fn test_span_ends_with_response_body() {
+        let body = http_body_util::Full::new(bytes::Bytes::from_static(b"hello"));
+
+        let (ended_before_body, span) = response_body_span(body, true).await;
+
+        assert!(ended_before_body == 0);
+        assert!(
+            span_attribute(&span, semconv::HTTP_RESPONSE_BODY_SIZE) == Some(5.into())
+        );
+        assert!(span_attribute(&span, semconv::ERROR_TYPE).is_none());
+        assert!(
+            span.events
+                .iter()
+                .any(|event| event.name == "http.response.first_byte")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_disconnect() {
+        let body = http_body_util::Full::new(bytes::Bytes::from_static(b"hello"));
+
+        let (_, span) = response_body_span(body, false).await;
+
+        assert!(
+            span_attribute(&span, semconv::ERROR_TYPE)
+                == Some("client_disconnected".into())
+        );
+        assert!(matches!(
+            span.status,
+            opentelemetry::trace::Status::Error { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_response_body_error() {
+        let frames = [
+            Ok(http_body::Frame::data(bytes::Bytes::from_static(b"he"))),
+            Err(std::io::Error::other("stream failed")),
+        ];
+        let body = http_body_util::StreamBody::new(futures_util::stream::iter(frames));
+
+        let (_, span) = response_body_span(body, true).await;
+
+        assert!(span_attribute(&span, semconv::ERROR_TYPE) == Some("_OTHER".into()));
+        assert!(
+            span.status
+                == opentelemetry::trace::Status::error("stream failed".to_owned())
+        );
+    }
+
+    #[rstest]
+    #[case::propagated(false)]
+    #[case::caught(true)]
+    #[tokio::test]
+    async fn test_handler_panic(#[case] caught: bool) {
+        crate::panic::install_hook();
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let layer = OtelAxumLayer::new(MatchedPath::as_str);
+        let service = layer.layer(tower::service_fn(move |_: Request<()>| async move {
+            let handler = || -> Response<String> { panic!("handler failed") };
+            if !caught {
+                handler();
+            }
+            // e.g. `tower_http::catch_panic` turning the panic into an error response
+            let response = std::panic::catch_unwind(handler).unwrap_or_else(|_| {
+                Response::builder().status(500).body(String::new()).unwrap()
+            });
+            Ok::<_, Infallible>(response)
+        }));
+        // the runtime is single-threaded: the task records spans with the subscriber
+        let result = tokio::spawn(service.oneshot(Request::new(()))).await;
+        assert!(result.is_err_and(|err| err.is_panic()) != caught);
+
+        provider.force_flush().unwrap();
+        let spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        let event = spans[0]
+            .events
+            .iter()
+            .find(|event| event.name == "exception");
+        assert!(let Some(event) = event);
+        let event_attribute = |key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.to_string())
+        };
+        assert!(
+            event_attribute(semconv::EXCEPTION_MESSAGE) == Some("handler failed".into())
+        );
+        assert!(event_attribute(semconv::EXCEPTION_TYPE) == Some("panic".into()));
+        assert!(event_attribute(semconv::EXCEPTION_STACKTRACE).is_some());
+        assert!(span_attribute(&spans[0], semconv::ERROR_TYPE) == Some("panic".into()));
+        assert!(spans[0].status == opentelemetry::trace::Status::error("handler failed"));
+    }
+
+    #[rstest]
+    #[case(Some("abc-123"))]
+    #[case(None)]
+    #[tokio::test]
+    async fn test_request_id(#[case] inbound: Option<&str>) {
+        use crate::http::RequestId;
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let layer = OtelAxumLayer::new(MatchedPath::as_str).request_id(
+            RequestIdHeader::x_request_id().generator(RequestIdGenerator::TraceId),
+        );
+        let mut req = Request::builder().uri("/");
+        if let Some(inbound) = inbound {
+            req = req.header("x-request-id", inbound);
+        }
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let service = layer.layer(tower::service_fn(|req: Request<()>| async move {
+            let request_id = req.extensions().get::<RequestId>().cloned();
+            assert!(request_id.is_some());
+            assert!(RequestId::current() == request_id);
+            // the request id is inherited by the children of the server span
+            let child = tracing::info_span!("child");
+            assert!(RequestId::from_context(&child.context()) == request_id);
+            Ok::<_, Infallible>(Response::new(String::new()))
+        }));
+        let response = service.oneshot(req.body(()).unwrap()).await.unwrap();
+        let header = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        drop(response);
+
+        provider.force_flush().unwrap();
+        let spans = exporter.get_finished_spans().unwrap();
+        let span = spans.iter().find(|span| span.name == "GET").unwrap();
+        let expected = inbound
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| span.span_context.trace_id().to_string());
+        assert!(header == expected);
+        assert!(span_attribute(span, "request.id") == Some(expected.into()));
+        assert!(RequestId::current().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_server_metrics() {
+        use opentelemetry::metrics::MeterProvider as _;
+        use opentelemetry_sdk::metrics::{
+            InMemoryMetricExporter, SdkMeterProvider,
+            data::{AggregatedMetrics, MetricData},
+        };
+
+        let exporter = InMemoryMetricExporter::default();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(exporter.clone())
+            .build();
+        let layer =
+            OtelAxumLayer::new(MatchedPath::as_str).meter(&meter_provider.meter("test"));
+        // metrics are recorded even for requests of traces which are not sampled
+        let req = Request::builder()
+            .method("POST")
+            .uri("/")
+            .header("traceparent", format!("00-{TRACE_ID}-{SPAN_ID}-00"))
+            .header("content-length", "5")
+            .body(())
+            .unwrap();
+
+        let service = layer.layer(tower::service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(String::new()))
+        }));
+        service.oneshot(req).await.unwrap();
+        meter_provider.force_flush().unwrap();
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let metric = |name: &str| {
+            metrics
+                .iter()
+                .flat_map(|resource| resource.scope_metrics())
+                .flat_map(|scope| scope.metrics())
+                .find(|metric| metric.name() == name)
+                .map(|metric| metric.data())
+                .unwrap()
+        };
+
+        let AggregatedMetrics::F64(MetricData::Histogram(duration)) =
+            metric("http.server.request.duration")
+        else {
+            panic!("unexpected request duration data");
+        };
+        let point = duration.data_points().next().unwrap();
+        let mut attributes = point
+            .attributes()
+            .map(|kv| (kv.key.as_str().to_owned(), kv.value.to_string()))
+            .collect::<Vec<_>>();
+        attributes.sort();
+        assert!(point.count() == 1);
+        assert!(
+            attributes
+                == [
+                    ("http.request.method".to_owned(), "POST".to_owned()),
+                    ("http.response.status_code".to_owned(), "200".to_owned()),
+                    ("url.scheme".to_owned(), "http".to_owned()),
+                ]
+        );
+
+        let AggregatedMetrics::I64(MetricData::Sum(active_requests)) =
+            metric("http.server.active_requests")
+        else {
+            panic!("unexpected active requests data");
+        };
+        assert!(active_requests.data_points().next().unwrap().value() == 0);
+
+        let AggregatedMetrics::U64(MetricData::Histogram(request_body_size)) =
+            metric("http.server.request.body.size")
+        else {
+            panic!("unexpected request body size data");
+        };
+        assert!(request_body_size.data_points().next().unwrap().sum() == 5);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_server_metrics_with_late_global_meter_provider() {
+        use opentelemetry_sdk::metrics::{InMemoryMetricExporter, SdkMeterProvider};
+
+        // the layer is created before the global meter provider is set
+        let layer = OtelAxumLayer::new(MatchedPath::as_str);
+        let exporter = InMemoryMetricExporter::default();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(exporter.clone())
+            .build();
+        opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+        let service = layer.layer(tower::service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(String::new()))
+        }));
+        service
+            .oneshot(Request::builder().uri("/").body(()).unwrap())
+            .await
+            .unwrap();
+        meter_provider.force_flush().unwrap();
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let scope = metrics
+            .iter()
+            .flat_map(|resource| resource.scope_metrics())
+            .find(|scope| {
+                scope
+                    .metrics()
+                    .any(|metric| metric.name() == "http.server.request.duration")
+            })
+            .unwrap();
+
+        assert!(scope.scope().name() == env!("CARGO_PKG_NAME"));
+    }
+}
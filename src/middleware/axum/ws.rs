@@ -0,0 +1,555 @@
+//! WebSocket instrumentation for axum.
+//!
+//! Upgraded connections outlive the upgrade request, whose span ends with the
+//! `101 Switching Protocols` response. [`OtelWebSocketUpgrade`] replaces axum's
+//! [`WebSocketUpgrade`] extractor to trace the connection in a span of its own,
+//! linked to the span of the upgrade request.
+
+use axum::{
+    Error,
+    extract::{
+        FromRequestParts, MatchedPath,
+        ws::{
+            CloseFrame, Message, WebSocket, WebSocketUpgrade,
+            rejection::WebSocketUpgradeRejection,
+        },
+    },
+    response::Response,
+};
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use http::request::Parts;
+use opentelemetry::{
+    KeyValue,
+    trace::{SpanKind, Status, TraceContextExt},
+};
+use std::{
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use tracing::{Instrument, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_opentelemetry_instrumentation_sdk::TRACING_TARGET;
+
+use crate::semconv;
+
+const PROTOCOL_NAME: &str = "websocket";
+const MESSAGE_EVENT: &str = "websocket.message";
+const MESSAGE_TYPE: &str = "websocket.message.type";
+const MESSAGE_OPCODE: &str = "websocket.message.opcode";
+const MESSAGE_SIZE: &str = "websocket.message.size";
+const CLOSE_CODE: &str = "websocket.close.code";
+const CLOSE_REASON: &str = "websocket.close.reason";
+const OTHER_ERROR_TYPE: &str = "_OTHER";
+
+/// Close code reported when a connection ends without a close frame.
+const ABNORMAL_CLOSURE: u16 = 1006;
+
+/// Extractor for WebSocket upgrades traced with OpenTelemetry.
+///
+/// Wraps axum's [`WebSocketUpgrade`], capturing the span of the upgrade request,
+/// e.g. the server span of [`OtelAxumLayer`](super::OtelAxumLayer). Connections
+/// accepted with [`OtelWebSocketUpgrade::on_upgrade`] are passed to the callback as
+/// an [`OtelWebSocket`], see its documentation for the recorded telemetry.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{Router, extract::ws::Message, response::Response, routing::get};
+/// use telemetry_rust::middleware::axum::ws::{OtelWebSocket, OtelWebSocketUpgrade};
+///
+/// async fn handler(ws: OtelWebSocketUpgrade) -> Response {
+///     ws.on_upgrade(echo)
+/// }
+///
+/// async fn echo(mut socket: OtelWebSocket) {
+///     while let Some(Ok(message)) = socket.recv().await {
+///         if let Message::Text(_) = message
+///             && socket.send(message).await.is_err()
+///         {
+///             return;
+///         }
+///     }
+/// }
+///
+/// let app: Router = Router::new().route("/ws", get(handler));
+/// ```
+#[derive(Debug)]
+pub struct OtelWebSocketUpgrade {
+    inner: WebSocketUpgrade,
+    span: Span,
+    route: Option<String>,
+}
+
+impl OtelWebSocketUpgrade {
+    /// Configures the wrapped [`WebSocketUpgrade`], e.g. its buffer sizes or protocols.
+    pub fn map(self, f: impl FnOnce(WebSocketUpgrade) -> WebSocketUpgrade) -> Self {
+        Self {
+            inner: f(self.inner),
+            ..self
+        }
+    }
+
+    /// Finalizes upgrading the connection and calls the callback with the socket.
+    ///
+    /// The future returned by the callback is instrumented with the connection span.
+    #[must_use = "to set up the WebSocket connection, this response must be returned"]
+    pub fn on_upgrade<C, Fut>(self, callback: C) -> Response
+    where
+        C: FnOnce(OtelWebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let Self { inner, span, route } = self;
+        inner.on_upgrade(move |socket| {
+            let socket = OtelWebSocket::with_route(socket, &span, route.as_deref());
+            let span = socket.span().clone();
+            callback(socket).instrument(span)
+        })
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for OtelWebSocketUpgrade {
+    type Rejection = WebSocketUpgradeRejection;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let inner = WebSocketUpgrade::from_request_parts(parts, state).await?;
+        let route = parts.extensions.get::<MatchedPath>();
+        Ok(Self {
+            inner,
+            span: Span::current(),
+            route: route.map(|route| route.as_str().to_owned()),
+        })
+    }
+}
+
+/// Direction of a WebSocket message, recorded as `websocket.message.type`.
+#[derive(Debug, Clone, Copy)]
+enum MessageType {
+    Sent,
+    Received,
+}
+
+impl MessageType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageType::Sent => "SENT",
+            MessageType::Received => "RECEIVED",
+        }
+    }
+}
+
+/// WebSocket connection traced with OpenTelemetry.
+///
+/// Wraps a socket, by default axum's [`WebSocket`], in a long-lived server span
+/// starting a new trace linked to the span of the upgrade request. The span records:
+///
+/// - each message sent or received as a `websocket.message` event, with the
+///   direction (`SENT` or `RECEIVED`) as `websocket.message.type`, the opcode as
+///   `websocket.message.opcode` and the payload size as `websocket.message.size`
+/// - the code and reason of the first close frame sent or received as
+///   `websocket.close.code` and `websocket.close.reason`, or the `1006` code if the
+///   socket is dropped before a close frame
+/// - socket errors, marking the span as failed
+///
+/// Close codes reporting a failure, i.e. all codes from `1002` to `1015` but `1005`,
+/// mark the span as failed with the code as `error.type`. The span ends when the
+/// socket is dropped.
+///
+/// Like the wrapped socket, [`OtelWebSocket`] implements [`Stream`] and [`Sink`], so
+/// it can be split to read and write concurrently.
+#[derive(Debug)]
+pub struct OtelWebSocket<S = WebSocket> {
+    inner: S,
+    span: Span,
+    closed: bool,
+    failed: bool,
+}
+
+impl<S> OtelWebSocket<S> {
+    /// Wraps a socket accepted by the upgrade request traced by `upgrade_span`.
+    pub fn new(socket: S, upgrade_span: &Span) -> Self {
+        Self::with_route(socket, upgrade_span, None)
+    }
+
+    fn with_route(socket: S, upgrade_span: &Span, route: Option<&str>) -> Self {
+        let name = match route {
+            Some(route) => format!("WebSocket {route}"),
+            None => "WebSocket".to_owned(),
+        };
+        let span = tracing::trace_span!(
+            target: TRACING_TARGET,
+            parent: None,
+            "WebSocket connection",
+            "otel.kind" = ?SpanKind::Server,
+            "otel.name" = name,
+            { semconv::NETWORK_PROTOCOL_NAME } = PROTOCOL_NAME,
+        );
+        if let Some(route) = route {
+            span.set_attribute(semconv::HTTP_ROUTE, route.to_owned());
+        }
+        let upgrade_context = upgrade_span.context();
+        let upgrade_span_context = upgrade_context.span().span_context().clone();
+        if upgrade_span_context.is_valid() {
+            span.add_link(upgrade_span_context);
+        }
+        Self {
+            inner: socket,
+            span,
+            closed: false,
+            failed: false,
+        }
+    }
+
+    /// Returns the span of the connection.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    fn record_message(&mut self, message_type: MessageType, message: &Message) {
+        let (opcode, size) = match message {
+            Message::Text(text) => ("text", text.as_str().len()),
+            Message::Binary(data) => ("binary", data.len()),
+            Message::Ping(data) => ("ping", data.len()),
+            Message::Pong(data) => ("pong", data.len()),
+            Message::Close(frame) => {
+                ("close", frame.as_ref().map_or(0, close_frame_size))
+            }
+        };
+        self.span.add_event(
+            MESSAGE_EVENT,
+            vec![
+                KeyValue::new(MESSAGE_TYPE, message_type.as_str()),
+                KeyValue::new(MESSAGE_OPCODE, opcode),
+                KeyValue::new(MESSAGE_SIZE, size as i64),
+            ],
+        );
+        if let Message::Close(frame) = message {
+            match frame {
+                Some(frame) => self.record_close(frame.code, Some(frame.reason.as_str())),
+                // close frames without status are reported with the 1005 code
+                None => self.record_close(1005, None),
+            }
+        }
+    }
+
+    fn record_close(&mut self, code: u16, reason: Option<&str>) {
+        if std::mem::replace(&mut self.closed, true) {
+            return;
+        }
+        self.span.set_attribute(CLOSE_CODE, i64::from(code));
+        if let Some(reason) = reason.filter(|reason| !reason.is_empty()) {
+            self.span.set_attribute(CLOSE_REASON, reason.to_owned());
+        }
+        if is_error(code) && !self.failed {
+            self.span
+                .set_attribute(semconv::ERROR_TYPE, code.to_string());
+            let description =
+                reason.map_or_else(|| format!("closed with code {code}"), str::to_owned);
+            self.span.set_status(Status::error(description));
+        }
+    }
+
+    fn record_error(&mut self, error: &impl Display) {
+        self.failed = true;
+        self.span
+            .set_attribute(semconv::ERROR_TYPE, OTHER_ERROR_TYPE);
+        self.span.set_status(Status::error(error.to_string()));
+    }
+}
+
+impl<S> OtelWebSocket<S>
+where
+    S: Stream<Item = Result<Message, Error>> + Sink<Message, Error = Error> + Unpin,
+{
+    /// Receives another message.
+    ///
+    /// Returns `None` if the stream has closed.
+    pub async fn recv(&mut self) -> Option<Result<Message, Error>> {
+        self.next().await
+    }
+
+    /// Sends a message.
+    pub async fn send(&mut self, message: Message) -> Result<(), Error> {
+        SinkExt::send(self, message).await
+    }
+}
+
+impl OtelWebSocket {
+    /// Returns the selected WebSocket subprotocol, if one has been chosen.
+    pub fn protocol(&self) -> Option<&http::HeaderValue> {
+        self.inner.protocol()
+    }
+}
+
+impl<S> Drop for OtelWebSocket<S> {
+    fn drop(&mut self) {
+        self.record_close(ABNORMAL_CLOSURE, None);
+    }
+}
+
+impl<S> Stream for OtelWebSocket<S>
+where
+    S: Stream<Item = Result<Message, Error>> + Unpin,
+{
+    type Item = Result<Message, Error>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let item = ready!(self.inner.poll_next_unpin(cx));
+        match &item {
+            Some(Ok(message)) => self.record_message(MessageType::Received, message),
+            Some(Err(err)) => self.record_error(err),
+            None => {}
+        }
+        Poll::Ready(item)
+    }
+}
+
+impl<S> Sink<Message> for OtelWebSocket<S>
+where
+    S: Sink<Message, Error = Error> + Unpin,
+{
+    type Error = Error;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let result = ready!(self.inner.poll_ready_unpin(cx));
+        Poll::Ready(self.inspect_error(result))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, message: Message) -> Result<(), Self::Error> {
+        self.record_message(MessageType::Sent, &message);
+        let result = self.inner.start_send_unpin(message);
+        self.inspect_error(result)
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let result = ready!(self.inner.poll_flush_unpin(cx));
+        Poll::Ready(self.inspect_error(result))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        let result = ready!(self.inner.poll_close_unpin(cx));
+        Poll::Ready(self.inspect_error(result))
+    }
+}
+
+impl<S> OtelWebSocket<S> {
+    fn inspect_error(&mut self, result: Result<(), Error>) -> Result<(), Error> {
+        if let Err(err) = &result {
+            self.record_error(err);
+        }
+        result
+    }
+}
+
+/// Size of the payload of a close frame: a 2 bytes code and the reason.
+fn close_frame_size(frame: &CloseFrame) -> usize {
+    2 + frame.reason.as_str().len()
+}
+
+/// Returns whether a close code reports a failure.
+fn is_error(code: u16) -> bool {
+    (1002..=1015).contains(&code) && code != 1005
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use futures_util::stream;
+    use opentelemetry::trace::{SpanId, TracerProvider as _};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+    use rstest::rstest;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    /// In-memory socket receiving the given messages and collecting the sent ones.
+    struct TestSocket {
+        received: stream::Iter<std::vec::IntoIter<Result<Message, Error>>>,
+        sent: Vec<Message>,
+    }
+
+    impl Stream for TestSocket {
+        type Item = Result<Message, Error>;
+
+        fn poll_next(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Option<Self::Item>> {
+            self.received.poll_next_unpin(cx)
+        }
+    }
+
+    impl Sink<Message> for TestSocket {
+        type Error = Error;
+
+        fn poll_ready(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, message: Message) -> Result<(), Error> {
+            self.sent.push(message);
+            Ok(())
+        }
+
+        fn poll_flush(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<Result<(), Error>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    async fn connection_spans(
+        received: Vec<Result<Message, Error>>,
+        sent: Vec<Message>,
+    ) -> Vec<SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let upgrade_span = tracing::info_span!("upgrade");
+        let socket = TestSocket {
+            received: stream::iter(received),
+            sent: Vec::new(),
+        };
+        let mut socket = OtelWebSocket::with_route(socket, &upgrade_span, Some("/ws"));
+        drop(upgrade_span);
+        while let Some(Ok(_)) = socket.recv().await {}
+        for message in sent {
+            socket.send(message).await.unwrap();
+        }
+        drop(socket);
+
+        provider.force_flush().unwrap();
+        exporter.get_finished_spans().unwrap()
+    }
+
+    fn span_attribute(span: &SpanData, key: &str) -> Option<opentelemetry::Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.clone())
+    }
+
+    #[tokio::test]
+    async fn test_connection_span() {
+        let received = vec![
+            Ok(Message::text("hello")),
+            Ok(Message::Close(Some(CloseFrame {
+                code: 1000,
+                reason: "bye".into(),
+            }))),
+        ];
+        let sent = vec![Message::binary(vec![1, 2, 3])];
+
+        let spans = connection_spans(received, sent).await;
+
+        assert!(let [upgrade, connection] = spans.as_slice());
+        assert!(connection.name == "WebSocket /ws");
+        assert!(connection.span_kind == SpanKind::Server);
+        assert!(connection.parent_span_id == SpanId::INVALID);
+        assert!(connection.span_context.trace_id() != upgrade.span_context.trace_id());
+        assert!(let [link] = connection.links.links.as_slice());
+        assert!(link.span_context == upgrade.span_context);
+        assert!(span_attribute(connection, semconv::HTTP_ROUTE) == Some("/ws".into()));
+        assert!(span_attribute(connection, CLOSE_CODE) == Some(1000.into()));
+        assert!(span_attribute(connection, CLOSE_REASON) == Some("bye".into()));
+        assert!(connection.status == Status::Unset);
+
+        let messages: Vec<_> = connection
+            .events
+            .iter()
+            .map(|event| {
+                let attribute = |key: &str| {
+                    event
+                        .attributes
+                        .iter()
+                        .find(|kv| kv.key.as_str() == key)
+                        .map(|kv| kv.value.to_string())
+                        .unwrap_or_default()
+                };
+                assert!(event.name == MESSAGE_EVENT);
+                (
+                    attribute(MESSAGE_TYPE),
+                    attribute(MESSAGE_OPCODE),
+                    attribute(MESSAGE_SIZE),
+                )
+            })
+            .collect();
+        let expected = [
+            ("RECEIVED", "text", "5"),
+            ("RECEIVED", "close", "5"),
+            ("SENT", "binary", "3"),
+        ]
+        .map(|(t, o, s)| (t.to_owned(), o.to_owned(), s.to_owned()));
+        assert!(messages == expected);
+    }
+
+    #[rstest]
+    #[case(vec![], 1006, Some("closed with code 1006"))]
+    #[case(vec![Ok(Message::Close(None))], 1005, None)]
+    #[case(vec![Ok(Message::Close(Some(CloseFrame { code: 1011, reason: "failed".into() })))], 1011, Some("failed"))]
+    #[tokio::test]
+    async fn test_close_code(
+        #[case] received: Vec<Result<Message, Error>>,
+        #[case] code: i64,
+        #[case] error: Option<&str>,
+    ) {
+        let spans = connection_spans(received, vec![]).await;
+
+        assert!(let [_, connection] = spans.as_slice());
+        assert!(span_attribute(connection, CLOSE_CODE) == Some(code.into()));
+        match error {
+            Some(error) => {
+                assert!(connection.status == Status::error(error.to_owned()));
+                assert!(
+                    span_attribute(connection, semconv::ERROR_TYPE)
+                        == Some(code.to_string().into())
+                );
+            }
+            None => assert!(connection.status == Status::Unset),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_socket_error() {
+        let received = vec![Err(Error::new(std::io::Error::other("connection reset")))];
+
+        let spans = connection_spans(received, vec![]).await;
+
+        assert!(let [_, connection] = spans.as_slice());
+        assert!(connection.status == Status::error("connection reset"));
+        assert!(span_attribute(connection, CLOSE_CODE) == Some(1006.into()));
+        assert!(span_attribute(connection, semconv::ERROR_TYPE) == Some("_OTHER".into()));
+    }
+}
@@ -0,0 +1,187 @@
+use bytes::Buf;
+use http_body::{Body, Frame, SizeHint};
+use opentelemetry::{KeyValue, trace::Status};
+use pin_project_lite::pin_project;
+use std::{
+    fmt::Display,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use super::metrics::RequestMetrics;
+use crate::semconv;
+
+/// Span event recorded when the first byte of the response body is sent.
+const FIRST_BYTE_EVENT: &str = "http.response.first_byte";
+/// Attribute of [`FIRST_BYTE_EVENT`] with the time since the request was received.
+const TIME_TO_FIRST_BYTE_ATTRIBUTE: &str = "http.response.time_to_first_byte";
+/// Error type of requests whose response body was dropped before it was fully sent.
+const CLIENT_DISCONNECTED: &str = "client_disconnected";
+const OTHER_ERROR_TYPE: &str = "_OTHER";
+
+pin_project! {
+    /// Response body of [`OtelHttpServerService`](super::OtelHttpServerService).
+    ///
+    /// Keeps the server span of the request open until the body is fully sent,
+    /// recording its size as `http.response.body.size`. The time to first byte is
+    /// recorded as the `http.response.first_byte` span event.
+    ///
+    /// Body errors mark the span as failed. If the body is dropped before it is fully
+    /// sent, e.g. because the client disconnected, the span is marked as failed with
+    /// the `client_disconnected` error type.
+    pub struct ResponseBody<B> {
+        #[pin]
+        inner: B,
+        state: Option<BodyState>,
+    }
+
+    impl<B> PinnedDrop for ResponseBody<B> {
+        fn drop(this: Pin<&mut Self>) {
+            if let Some(state) = this.project().state.take() {
+                state.disconnected();
+            }
+        }
+    }
+}
+
+impl<B> ResponseBody<B> {
+    /// Wraps the body of a request which is not traced.
+    pub(crate) fn untraced(inner: B) -> Self {
+        Self { inner, state: None }
+    }
+}
+
+impl<B: Body> ResponseBody<B> {
+    /// Wraps the body of a traced request, ending the span and the request metrics
+    /// once the body is fully sent.
+    ///
+    /// `expects_body` is `false` for responses sent without body, e.g. to `HEAD`
+    /// requests, which are never polled.
+    pub(crate) fn traced(
+        inner: B,
+        span: Span,
+        started: Instant,
+        metrics: RequestMetrics,
+        expects_body: bool,
+    ) -> Self {
+        let state = BodyState {
+            span,
+            started,
+            metrics,
+            size: 0,
+        };
+        if expects_body && !inner.is_end_stream() {
+            Self {
+                inner,
+                state: Some(state),
+            }
+        } else {
+            state.end();
+            Self::untraced(inner)
+        }
+    }
+}
+
+impl<B> Body for ResponseBody<B>
+where
+    B: Body,
+    B::Error: Display,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let span = this.state.as_ref().map(|state| state.span.clone());
+        let _guard = span.as_ref().map(Span::enter);
+        let result = futures_util::ready!(this.inner.as_mut().poll_frame(cx));
+
+        match &result {
+            Some(Ok(frame)) => {
+                if let Some(state) = this.state.as_mut()
+                    && let Some(data) = frame.data_ref()
+                {
+                    state.sent(data.remaining());
+                }
+                if this.inner.is_end_stream()
+                    && let Some(state) = this.state.take()
+                {
+                    state.end();
+                }
+            }
+            Some(Err(err)) => {
+                if let Some(state) = this.state.take() {
+                    state.failed(err);
+                }
+            }
+            None => {
+                if let Some(state) = this.state.take() {
+                    state.end();
+                }
+            }
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Progress of a traced response body.
+struct BodyState {
+    span: Span,
+    started: Instant,
+    metrics: RequestMetrics,
+    size: u64,
+}
+
+impl BodyState {
+    fn sent(&mut self, bytes: usize) {
+        if self.size == 0 && bytes > 0 {
+            self.span.add_event(
+                FIRST_BYTE_EVENT,
+                vec![KeyValue::new(
+                    TIME_TO_FIRST_BYTE_ATTRIBUTE,
+                    self.started.elapsed().as_secs_f64(),
+                )],
+            );
+        }
+        self.size += bytes as u64;
+    }
+
+    fn end(self) {
+        self.span
+            .set_attribute(semconv::HTTP_RESPONSE_BODY_SIZE, self.size as i64);
+        self.metrics.end(Some(self.size));
+    }
+
+    fn failed(mut self, error: &impl Display) {
+        self.span
+            .set_attribute(semconv::ERROR_TYPE, OTHER_ERROR_TYPE);
+        self.span.set_status(Status::error(error.to_string()));
+        self.metrics.set_error();
+        self.metrics.end(None);
+    }
+
+    fn disconnected(mut self) {
+        self.span
+            .set_attribute(semconv::HTTP_RESPONSE_BODY_SIZE, self.size as i64);
+        self.span
+            .set_attribute(semconv::ERROR_TYPE, CLIENT_DISCONNECTED);
+        self.span.set_status(Status::error("client disconnected"));
+        self.metrics.set_error_type(CLIENT_DISCONNECTED);
+        self.metrics.end(None);
+    }
+}
@@ -0,0 +1,211 @@
+use http::{HeaderMap, header::FORWARDED};
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_REAL_IP: &str = "x-real-ip";
+
+/// Address of a node in the chain of proxies of a request.
+pub(crate) type NodeAddress = (IpAddr, Option<u16>);
+
+/// Policy deciding which proxies are trusted to report the client address.
+///
+/// The client address of a request is read from the `Forwarded` header, or else from
+/// the `X-Forwarded-For` header, or else from the `X-Real-IP` header. These headers
+/// can be set by any client, so they are only used behind trusted proxies:
+/// - [`TrustedProxies::none`] ignores the headers, the client is the peer address
+/// - [`TrustedProxies::count`] trusts a fixed number of proxies in front of the service
+/// - [`TrustedProxies::networks`] trusts proxies with addresses in the given networks
+///
+/// Without trusted proxies, the client address is the peer address, see
+/// [`OtelHttpServerLayer::peer_address`](super::OtelHttpServerLayer::peer_address).
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::middleware::http_server::{IpNet, TrustedProxies};
+///
+/// let trusted = TrustedProxies::networks(["10.0.0.0/8".parse::<IpNet>()?]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrustedProxies(Trust);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum Trust {
+    #[default]
+    None,
+    Count(usize),
+    Networks(Vec<IpNet>),
+}
+
+impl TrustedProxies {
+    /// Trusts no proxy, proxy headers are ignored.
+    ///
+    /// This is the default policy.
+    pub fn none() -> Self {
+        Self(Trust::None)
+    }
+
+    /// Trusts the given number of proxies in front of the service, including the peer.
+    ///
+    /// E.g. with a single load balancer, the client is the last address it appended
+    /// to `X-Forwarded-For`.
+    pub fn count(proxies: usize) -> Self {
+        Self(Trust::Count(proxies))
+    }
+
+    /// Trusts proxies with addresses in any of the given networks.
+    ///
+    /// The client is the last address of the chain not belonging to a trusted network.
+    /// Proxy headers are ignored if the peer address is not available or not trusted.
+    pub fn networks(networks: impl IntoIterator<Item = IpNet>) -> Self {
+        Self(Trust::Networks(networks.into_iter().collect()))
+    }
+
+    /// Returns the client address of a request with the given headers and peer address.
+    pub(crate) fn client_address(
+        &self,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+    ) -> Option<NodeAddress> {
+        let peer = peer.map(|peer| (peer.ip(), Some(peer.port())));
+        match &self.0 {
+            Trust::None | Trust::Count(0) => peer,
+            Trust::Count(proxies) => {
+                let hops = forwarded_chain(headers);
+                let index = hops.len().saturating_sub(*proxies);
+                match hops.get(index) {
+                    Some(hop) => *hop,
+                    None => peer,
+                }
+            }
+            Trust::Networks(networks) => {
+                let is_trusted =
+                    |ip: &IpAddr| networks.iter().any(|net| net.contains(ip));
+                if !peer.is_some_and(|(ip, _)| is_trusted(&ip)) {
+                    return peer;
+                }
+                let hops = forwarded_chain(headers);
+                let mut client = peer;
+                for hop in hops.into_iter().rev() {
+                    client = hop;
+                    match hop {
+                        Some((ip, _)) if is_trusted(&ip) => continue,
+                        _ => break,
+                    }
+                }
+                client
+            }
+        }
+    }
+}
+
+/// Returns the addresses of the proxy chain reported by proxy headers, closest last.
+///
+/// Unknown or obfuscated addresses are `None`.
+fn forwarded_chain(headers: &HeaderMap) -> Vec<Option<NodeAddress>> {
+    let values = |name| {
+        headers
+            .get_all(name)
+            .into_iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect::<Vec<_>>()
+    };
+
+    let forwarded = values(FORWARDED.as_str());
+    if !forwarded.is_empty() {
+        return forwarded
+            .into_iter()
+            .map(|element| {
+                element
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .find(|(key, _)| key.trim().eq_ignore_ascii_case("for"))
+                    .and_then(|(_, node)| parse_node(node))
+            })
+            .collect();
+    }
+    let forwarded_for = values(X_FORWARDED_FOR);
+    if !forwarded_for.is_empty() {
+        return forwarded_for.into_iter().map(parse_node).collect();
+    }
+    values(X_REAL_IP)
+        .into_iter()
+        .take(1)
+        .map(parse_node)
+        .collect()
+}
+
+/// Parses an IP address with an optional port, e.g. `192.0.2.60`,
+/// `"[2001:db8:cafe::17]:4711"` or `203.0.113.43:80`.
+fn parse_node(node: &str) -> Option<NodeAddress> {
+    let node = node.trim().trim_matches('"');
+    if let Ok(ip) = node.parse::<IpAddr>() {
+        return Some((ip, None));
+    }
+    if let Ok(address) = node.parse::<SocketAddr>() {
+        return Some((address.ip(), Some(address.port())));
+    }
+    node.strip_prefix('[')
+        .and_then(|node| node.strip_suffix(']'))
+        .and_then(|ip| ip.parse().ok())
+        .map(|ip| (ip, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use http::{HeaderName, HeaderValue};
+    use rstest::rstest;
+
+    const PEER: &str = "10.0.0.2:4000";
+
+    #[rstest]
+    #[case(TrustedProxies::none(), &[("x-forwarded-for", "203.0.113.1")], Some("10.0.0.2:4000"))]
+    #[case(TrustedProxies::count(1), &[("x-forwarded-for", "198.51.100.7, 203.0.113.1")], Some("203.0.113.1"))]
+    #[case(TrustedProxies::count(2), &[("x-forwarded-for", "198.51.100.7, 203.0.113.1")], Some("198.51.100.7"))]
+    #[case(TrustedProxies::count(5), &[("x-forwarded-for", "198.51.100.7, 203.0.113.1")], Some("198.51.100.7"))]
+    #[case(TrustedProxies::count(1), &[], Some("10.0.0.2:4000"))]
+    #[case(TrustedProxies::count(1), &[("x-forwarded-for", "unknown")], None)]
+    #[case(TrustedProxies::count(1), &[("x-real-ip", "203.0.113.1")], Some("203.0.113.1"))]
+    #[case(
+        TrustedProxies::count(1),
+        &[("forwarded", r#"for=198.51.100.7;proto=https, for="[2001:db8:cafe::17]:4711""#), ("x-forwarded-for", "203.0.113.1")],
+        Some("[2001:db8:cafe::17]:4711"),
+    )]
+    #[case(
+        TrustedProxies::networks(["10.0.0.0/8".parse().unwrap()]),
+        &[("x-forwarded-for", "198.51.100.7, 203.0.113.1, 10.1.0.1")],
+        Some("203.0.113.1"),
+    )]
+    #[case(
+        TrustedProxies::networks(["10.0.0.0/8".parse().unwrap()]),
+        &[("x-forwarded-for", "10.3.0.1, 10.1.0.1")],
+        Some("10.3.0.1"),
+    )]
+    #[case(
+        TrustedProxies::networks(["192.168.0.0/16".parse().unwrap()]),
+        &[("x-forwarded-for", "203.0.113.1")],
+        Some("10.0.0.2:4000"),
+    )]
+    fn test_client_address(
+        #[case] trusted: TrustedProxies,
+        #[case] headers: &[(&str, &str)],
+        #[case] expected: Option<&str>,
+    ) {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_str(value).unwrap(),
+            );
+        }
+
+        let client = trusted.client_address(&header_map, PEER.parse().ok());
+
+        assert!(client == expected.and_then(parse_node));
+    }
+}
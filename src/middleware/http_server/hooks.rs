@@ -0,0 +1,104 @@
+use http::{Request, Response};
+use tracing::Span;
+
+/// Predicate selecting the requests traced by [`OtelHttpServerLayer`](super::OtelHttpServerLayer).
+///
+/// Implemented by closures `Fn(&Request<B>) -> bool`, returning `true` for requests
+/// which should be traced, and by `()`, which traces all requests.
+pub trait RequestFilter<B> {
+    /// Returns `true` if the request should be traced.
+    fn is_traced(&self, request: &Request<B>) -> bool;
+}
+
+impl<B> RequestFilter<B> for () {
+    #[inline]
+    fn is_traced(&self, _: &Request<B>) -> bool {
+        true
+    }
+}
+
+impl<B, F> RequestFilter<B> for F
+where
+    F: Fn(&Request<B>) -> bool,
+{
+    #[inline]
+    fn is_traced(&self, request: &Request<B>) -> bool {
+        self(request)
+    }
+}
+
+/// Hook called with each traced request and its server span, once the span has all
+/// the attributes recorded by [`OtelHttpServerLayer`](super::OtelHttpServerLayer).
+///
+/// Implemented by closures `Fn(&Request<B>, &Span)` and by `()`, which does nothing.
+pub trait OnRequest<B> {
+    /// Customizes the server span of the request.
+    fn on_request(&self, request: &Request<B>, span: &Span);
+}
+
+impl<B> OnRequest<B> for () {
+    #[inline]
+    fn on_request(&self, _: &Request<B>, _: &Span) {}
+}
+
+impl<B, F> OnRequest<B> for F
+where
+    F: Fn(&Request<B>, &Span),
+{
+    #[inline]
+    fn on_request(&self, request: &Request<B>, span: &Span) {
+        self(request, span)
+    }
+}
+
+/// Hook called with the response of each traced request and its server span.
+///
+/// Implemented by closures `Fn(&Response<B>, &Span)` and by `()`, which does nothing.
+pub trait OnResponse<B> {
+    /// Customizes the server span of the request with its response.
+    fn on_response(&self, response: &Response<B>, span: &Span);
+}
+
+impl<B> OnResponse<B> for () {
+    #[inline]
+    fn on_response(&self, _: &Response<B>, _: &Span) {}
+}
+
+impl<B, F> OnResponse<B> for F
+where
+    F: Fn(&Response<B>, &Span),
+{
+    #[inline]
+    fn on_response(&self, response: &Response<B>, span: &Span) {
+        self(response, span)
+    }
+}
+
+/// Extractor of the route of a request, recorded as `http.route` and in the span name.
+///
+/// The route is the matched path template, e.g. `/users/{id}`, rather than the
+/// actual request path, to keep the cardinality of span names and metrics low.
+///
+/// Implemented by closures `Fn(&Request<B>) -> Option<String>` and by `()`, which
+/// doesn't extract any route.
+pub trait RouteExtractor<B> {
+    /// Returns the route of the request, if known.
+    fn route(&self, request: &Request<B>) -> Option<String>;
+}
+
+impl<B> RouteExtractor<B> for () {
+    #[inline]
+    fn route(&self, _: &Request<B>) -> Option<String> {
+        None
+    }
+}
+
+impl<B, F> RouteExtractor<B> for F
+where
+    F: Fn(&Request<B>) -> Option<String>,
+{
+    #[inline]
+    fn route(&self, request: &Request<B>) -> Option<String> {
+        self(request)
+    }
+}
@@ -0,0 +1,202 @@
+use http::{HeaderMap, Method, Request, Response, header::CONTENT_LENGTH};
+use opentelemetry::{
+    KeyValue, Value, global,
+    metrics::{Histogram, Meter, UpDownCounter},
+};
+use opentelemetry_semantic_conventions::metric;
+use std::{
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+use crate::semconv;
+
+const OTHER_HTTP_METHOD: &str = "_OTHER";
+const OTHER_ERROR_TYPE: &str = "_OTHER";
+const DEFAULT_URL_SCHEME: &str = "http";
+
+/// Bucket boundaries of `http.server.request.duration` advised by semantic conventions.
+const DURATION_BOUNDARIES: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
+];
+
+/// HTTP server metrics, with instruments created on the first request.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ServerMetrics {
+    /// Meter creating the instruments, a meter of the global meter provider if `None`.
+    meter: Option<Meter>,
+    instruments: Arc<OnceLock<Instruments>>,
+}
+
+/// Instruments of the HTTP server metrics.
+#[derive(Debug, Clone)]
+struct Instruments {
+    request_duration: Histogram<f64>,
+    active_requests: UpDownCounter<i64>,
+    request_body_size: Histogram<u64>,
+    response_body_size: Histogram<u64>,
+}
+
+impl Instruments {
+    fn new(meter: &Meter) -> Self {
+        Self {
+            request_duration: meter
+                .f64_histogram(metric::HTTP_SERVER_REQUEST_DURATION)
+                .with_description("Duration of HTTP server requests.")
+                .with_unit("s")
+                .with_boundaries(DURATION_BOUNDARIES.to_vec())
+                .build(),
+            active_requests: meter
+                .i64_up_down_counter(metric::HTTP_SERVER_ACTIVE_REQUESTS)
+                .with_description("Number of active HTTP server requests.")
+                .with_unit("{request}")
+                .build(),
+            request_body_size: meter
+                .u64_histogram(metric::HTTP_SERVER_REQUEST_BODY_SIZE)
+                .with_description("Size of HTTP server request bodies.")
+                .with_unit("By")
+                .build(),
+            response_body_size: meter
+                .u64_histogram(metric::HTTP_SERVER_RESPONSE_BODY_SIZE)
+                .with_description("Size of HTTP server response bodies.")
+                .with_unit("By")
+                .build(),
+        }
+    }
+}
+
+impl ServerMetrics {
+    /// Metrics recorded with the given meter.
+    pub(crate) fn new(meter: &Meter) -> Self {
+        Self {
+            meter: Some(meter.clone()),
+            instruments: Arc::default(),
+        }
+    }
+
+    /// Metrics recorded with the global meter provider set at the time of the first
+    /// request.
+    pub(crate) fn global() -> Self {
+        Self::default()
+    }
+
+    fn instruments(&self) -> &Instruments {
+        self.instruments.get_or_init(|| match &self.meter {
+            Some(meter) => Instruments::new(meter),
+            None => Instruments::new(&global::meter(env!("CARGO_PKG_NAME"))),
+        })
+    }
+
+    /// Starts measuring a request, counting it as active until the returned
+    /// [`RequestMetrics`] is dropped.
+    pub(crate) fn start<B>(&self, req: &Request<B>, route: &str) -> RequestMetrics {
+        let active_attributes = vec![
+            KeyValue::new(semconv::HTTP_REQUEST_METHOD, semantic_method(req.method())),
+            KeyValue::new(
+                semconv::URL_SCHEME,
+                req.uri()
+                    .scheme_str()
+                    .unwrap_or(DEFAULT_URL_SCHEME)
+                    .to_owned(),
+            ),
+        ];
+        let instruments = self.instruments().clone();
+        instruments.active_requests.add(1, &active_attributes);
+        let mut attributes = active_attributes.clone();
+        if !route.is_empty() {
+            attributes.push(KeyValue::new(semconv::HTTP_ROUTE, route.to_owned()));
+        }
+        RequestMetrics {
+            instruments,
+            active_attributes,
+            attributes,
+            request_body_size: content_length(req.headers()),
+            start: Instant::now(),
+        }
+    }
+}
+
+/// Measurements of an in-flight request.
+pub(crate) struct RequestMetrics {
+    instruments: Instruments,
+    active_attributes: Vec<KeyValue>,
+    attributes: Vec<KeyValue>,
+    request_body_size: Option<u64>,
+    start: Instant,
+}
+
+impl RequestMetrics {
+    /// Adds the status of the response to the measurements.
+    pub(crate) fn set_response<B>(&mut self, response: &Response<B>) {
+        let status = response.status();
+        self.attributes.push(KeyValue::new(
+            semconv::HTTP_RESPONSE_STATUS_CODE,
+            i64::from(status.as_u16()),
+        ));
+        if status.is_server_error() {
+            self.set_error_type(status.as_u16().to_string());
+        }
+    }
+
+    /// Marks the request as failed with the given error type, unless it already failed.
+    pub(crate) fn set_error_type(&mut self, error_type: impl Into<Value>) {
+        if !self
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == semconv::ERROR_TYPE)
+        {
+            self.attributes
+                .push(KeyValue::new(semconv::ERROR_TYPE, error_type.into()));
+        }
+    }
+
+    /// Marks the request as failed without a response.
+    pub(crate) fn set_error(&mut self) {
+        self.set_error_type(OTHER_ERROR_TYPE);
+    }
+
+    /// Records the duration and body sizes of the request.
+    ///
+    /// The request body size is only known from the `Content-Length` header.
+    pub(crate) fn end(self, response_body_size: Option<u64>) {
+        let duration = self.start.elapsed().as_secs_f64();
+        let metrics = &self.instruments;
+        metrics.request_duration.record(duration, &self.attributes);
+        if let Some(size) = self.request_body_size {
+            metrics.request_body_size.record(size, &self.attributes);
+        }
+        if let Some(size) = response_body_size {
+            metrics.response_body_size.record(size, &self.attributes);
+        }
+    }
+}
+
+impl Drop for RequestMetrics {
+    fn drop(&mut self) {
+        self.instruments
+            .active_requests
+            .add(-1, &self.active_attributes);
+    }
+}
+
+fn semantic_method(method: &Method) -> &'static str {
+    match *method {
+        Method::CONNECT => "CONNECT",
+        Method::DELETE => "DELETE",
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::PATCH => "PATCH",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::TRACE => "TRACE",
+        _ => OTHER_HTTP_METHOD,
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
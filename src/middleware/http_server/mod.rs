@@ -0,0 +1,742 @@
+//! Generic HTTP server middleware.
+//!
+//! Provides a [`tower`] layer to automatically instrument HTTP requests with
+//! OpenTelemetry tracing and metrics, for any service handling [`http::Request`]s,
+//! e.g. plain hyper services. Framework integrations like
+//! [`OtelAxumLayer`](super::axum::OtelAxumLayer) are built on top of it.
+
+// Originally retired from davidB/tracing-opentelemetry-instrumentation-sdk
+// https://github.com/davidB/tracing-opentelemetry-instrumentation-sdk/blob/d3609ac2cc699d3a24fbf89754053cc8e938e3bf/axum-tracing-opentelemetry/src/middleware/trace_extractor.rs#L53
+// which is licensed under CC0 1.0 Universal
+// https://github.com/davidB/tracing-opentelemetry-instrumentation-sdk/blob/d3609ac2cc699d3a24fbf89754053cc8e938e3bf/LICENSE
+
+use http::{Extensions, Method, Request, Response};
+use opentelemetry::{
+    metrics::Meter,
+    trace::{TraceContextExt, TraceId},
+};
+use pin_project_lite::pin_project;
+use std::{
+    error::Error,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+use tracing::Span;
+use tracing_opentelemetry_instrumentation_sdk::http as otel_http;
+
+use crate::{
+    filter::{DebugTrigger, enable_debug_logging},
+    http::{HeaderCapture, RequestId},
+    semconv,
+};
+
+mod body;
+mod client_address;
+mod hooks;
+mod metrics;
+mod request_id;
+mod trace_response;
+mod trust;
+
+pub use body::ResponseBody;
+pub use client_address::TrustedProxies;
+pub use hooks::{OnRequest, OnResponse, RequestFilter, RouteExtractor};
+pub use ipnet::IpNet;
+use metrics::{RequestMetrics, ServerMetrics};
+pub use request_id::{RequestIdGenerator, RequestIdHeader};
+pub use trace_response::TraceResponseHeader;
+pub use trust::ContextTrust;
+
+/// Span attribute with the name of the propagator which extracted the incoming context,
+/// recorded if propagation diagnostics are enabled.
+const EXTRACTED_BY_ATTRIBUTE: &str = "propagation.extracted_by";
+/// Span attribute with the request id, recorded if a request id header is configured.
+const REQUEST_ID_ATTRIBUTE: &str = "request.id";
+
+/// Function type for extracting the peer address of a connection from request extensions.
+///
+/// With axum, the peer address is available if the app is served with
+/// `into_make_service_with_connect_info::<SocketAddr>()`:
+/// `|extensions| extensions.get::<ConnectInfo<SocketAddr>>().map(|info| info.0)`.
+/// With hyper, the address returned by the listener can be inserted into the request
+/// extensions by the service handling the connection.
+pub type PeerAddress = fn(&Extensions) -> Option<SocketAddr>;
+
+/// OpenTelemetry layer for HTTP servers.
+///
+/// This layer provides automatic tracing instrumentation for any
+/// [`tower::Service`] handling [`http::Request`]s, creating spans for HTTP requests
+/// with appropriate semantic attributes and recording the HTTP server metrics.
+///
+/// Requests have no route unless a [`RouteExtractor`] is set with
+/// [`OtelHttpServerLayer::route`].
+///
+/// Panics propagated by the inner service are recorded on the span as `exception`
+/// events, and resumed once recorded. With the hook of
+/// [`panic::install_hook`](crate::panic::install_hook), their backtrace and the panics
+/// caught by an inner layer, e.g. `tower_http::catch_panic`, are recorded as well.
+///
+/// # Example
+///
+/// ```rust
+/// use http::{Request, Response};
+/// use std::convert::Infallible;
+/// use telemetry_rust::middleware::http_server::OtelHttpServerLayer;
+/// use tower::{Layer, service_fn};
+///
+/// let layer = OtelHttpServerLayer::new().route(|req: &Request<String>| {
+///     req.uri()
+///         .path()
+///         .starts_with("/users/")
+///         .then(|| "/users/{id}".to_owned())
+/// });
+/// let service = layer.layer(service_fn(|_: Request<String>| async {
+///     Ok::<_, Infallible>(Response::new(String::new()))
+/// }));
+/// ```
+#[derive(Debug, Clone)]
+pub struct OtelHttpServerLayer<R = (), F = (), OnReq = (), OnRes = ()> {
+    route: R,
+    filter: F,
+    on_request: OnReq,
+    on_response: OnRes,
+    inject_context: bool,
+    debug_trigger: Option<DebugTrigger>,
+    context_trust: ContextTrust,
+    peer_address: Option<PeerAddress>,
+    trusted_proxies: TrustedProxies,
+    trace_response_headers: Vec<TraceResponseHeader>,
+    request_id: Option<RequestIdHeader>,
+    header_capture: HeaderCapture,
+    metrics: ServerMetrics,
+}
+
+// add a builder like api
+impl OtelHttpServerLayer {
+    /// Creates a new OpenTelemetry layer for HTTP servers.
+    pub fn new() -> Self {
+        OtelHttpServerLayer {
+            route: (),
+            filter: (),
+            on_request: (),
+            on_response: (),
+            inject_context: false,
+            debug_trigger: None,
+            context_trust: ContextTrust::always(),
+            peer_address: None,
+            trusted_proxies: TrustedProxies::none(),
+            trace_response_headers: Vec::new(),
+            request_id: None,
+            header_capture: HeaderCapture::server_from_env(),
+            metrics: ServerMetrics::global(),
+        }
+    }
+}
+
+impl Default for OtelHttpServerLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R, F, OnReq, OnRes> OtelHttpServerLayer<R, F, OnReq, OnRes> {
+    /// Sets the extractor of the route of each request.
+    ///
+    /// The route is recorded as `http.route`, in the span name and in the metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `route` - Extractor returning the route of a request, see [`RouteExtractor`]
+    pub fn route<R2>(self, route: R2) -> OtelHttpServerLayer<R2, F, OnReq, OnRes> {
+        self.with_hooks(|_, filter, on_request, on_response| {
+            (route, filter, on_request, on_response)
+        })
+    }
+
+    /// Sets a predicate to selectively trace requests.
+    ///
+    /// Requests which are not traced get neither a span nor metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Predicate that returns true for requests that should be traced,
+    ///   see [`RequestFilter`]
+    pub fn filter<F2>(self, filter: F2) -> OtelHttpServerLayer<R, F2, OnReq, OnRes> {
+        self.with_hooks(|route, _, on_request, on_response| {
+            (route, filter, on_request, on_response)
+        })
+    }
+
+    /// Sets a hook to customize the server span of each traced request, e.g. to add
+    /// attributes or rename the span.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_request` - Hook called with the request and its span, see [`OnRequest`]
+    pub fn on_request<OnReq2>(
+        self,
+        on_request: OnReq2,
+    ) -> OtelHttpServerLayer<R, F, OnReq2, OnRes> {
+        self.with_hooks(|route, filter, _, on_response| {
+            (route, filter, on_request, on_response)
+        })
+    }
+
+    /// Sets a hook to customize the server span of each traced request with its
+    /// response.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_response` - Hook called with the response and the span, see [`OnResponse`]
+    pub fn on_response<OnRes2>(
+        self,
+        on_response: OnRes2,
+    ) -> OtelHttpServerLayer<R, F, OnReq, OnRes2> {
+        self.with_hooks(|route, filter, on_request, _| {
+            (route, filter, on_request, on_response)
+        })
+    }
+
+    fn with_hooks<R2, F2, OnReq2, OnRes2>(
+        self,
+        hooks: impl FnOnce(R, F, OnReq, OnRes) -> (R2, F2, OnReq2, OnRes2),
+    ) -> OtelHttpServerLayer<R2, F2, OnReq2, OnRes2> {
+        let (route, filter, on_request, on_response) =
+            hooks(self.route, self.filter, self.on_request, self.on_response);
+        OtelHttpServerLayer {
+            route,
+            filter,
+            on_request,
+            on_response,
+            inject_context: self.inject_context,
+            debug_trigger: self.debug_trigger,
+            context_trust: self.context_trust,
+            peer_address: self.peer_address,
+            trusted_proxies: self.trusted_proxies,
+            trace_response_headers: self.trace_response_headers,
+            request_id: self.request_id,
+            header_capture: self.header_capture,
+            metrics: self.metrics,
+        }
+    }
+
+    /// Configures whether to inject OpenTelemetry context into responses.
+    ///
+    /// # Arguments
+    ///
+    /// * `inject_context` - Whether to inject trace context into response headers
+    pub fn inject_context(self, inject_context: bool) -> Self {
+        OtelHttpServerLayer {
+            inject_context,
+            ..self
+        }
+    }
+
+    /// Adds a response header reporting the trace of the request to the client.
+    ///
+    /// Can be called multiple times to add several headers, e.g. both the W3C
+    /// `traceresponse` and a custom `x-trace-id` header.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - Header to add to responses, see [`TraceResponseHeader`]
+    pub fn trace_response_header(mut self, header: TraceResponseHeader) -> Self {
+        self.trace_response_headers.push(header);
+        self
+    }
+
+    /// Enables request ids, read from the inbound header or generated.
+    ///
+    /// The request id is recorded as `request.id` on the server span, stored in the
+    /// request extensions as a [`RequestId`] and echoed in the
+    /// response header. It is also stored in the context of the server span, to be
+    /// logged by [`JsonFormat`](crate::fmt::JsonFormat) and sent by the instrumented
+    /// HTTP clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `request_id` - Header carrying request ids, see [`RequestIdHeader`]
+    pub fn request_id(self, request_id: RequestIdHeader) -> Self {
+        OtelHttpServerLayer {
+            request_id: Some(request_id),
+            ..self
+        }
+    }
+
+    /// Enables per-request debug logging for requests matching the trigger.
+    ///
+    /// Events inside the server span of a matching request are filtered with the
+    /// debug directives of the [`TracingFilter`](crate::filter::TracingFilter).
+    /// Debug logging is only enabled for sampled traces.
+    ///
+    /// # Arguments
+    ///
+    /// * `debug_trigger` - Baggage entry or header that enables debug logging
+    pub fn debug_trigger(self, debug_trigger: DebugTrigger) -> Self {
+        OtelHttpServerLayer {
+            debug_trigger: Some(debug_trigger),
+            ..self
+        }
+    }
+
+    /// Sets the policy deciding whether the trace context of incoming requests is trusted.
+    ///
+    /// Untrusted requests start a new trace linked to the incoming context.
+    /// All requests are trusted by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_trust` - Policy to trust the incoming context
+    pub fn context_trust(self, context_trust: ContextTrust) -> Self {
+        OtelHttpServerLayer {
+            context_trust,
+            ..self
+        }
+    }
+
+    /// Sets the function to read the peer address of a connection from request extensions.
+    ///
+    /// # Arguments
+    ///
+    /// * `peer_address` - Function returning the peer address, see [`PeerAddress`]
+    pub fn peer_address(self, peer_address: PeerAddress) -> Self {
+        OtelHttpServerLayer {
+            peer_address: Some(peer_address),
+            ..self
+        }
+    }
+
+    /// Sets the proxies trusted to report the client address in proxy headers.
+    ///
+    /// The client address is recorded as `client.address` and `client.port`, and the
+    /// peer address as `network.peer.address` and `network.peer.port`. No proxy is
+    /// trusted by default, so the client address is the peer address.
+    ///
+    /// # Arguments
+    ///
+    /// * `trusted_proxies` - Policy to trust proxy headers, see [`TrustedProxies`]
+    pub fn trusted_proxies(self, trusted_proxies: TrustedProxies) -> Self {
+        OtelHttpServerLayer {
+            trusted_proxies,
+            ..self
+        }
+    }
+
+    /// Sets the request and response headers recorded as span attributes.
+    ///
+    /// Defaults to the headers listed in the
+    /// `OTEL_INSTRUMENTATION_HTTP_SERVER_CAPTURE_HEADERS_*` environment variables,
+    /// see [`HeaderCapture::server_from_env`].
+    ///
+    /// # Arguments
+    ///
+    /// * `header_capture` - Allow-list of headers to record, see [`HeaderCapture`]
+    pub fn capture_headers(self, header_capture: HeaderCapture) -> Self {
+        OtelHttpServerLayer {
+            header_capture,
+            ..self
+        }
+    }
+
+    /// Sets the meter recording the HTTP server metrics.
+    ///
+    /// The layer records `http.server.request.duration`, `http.server.active_requests`,
+    /// `http.server.request.body.size` and `http.server.response.body.size` for every
+    /// request passing the filter, independently of trace sampling. Requests are
+    /// measured until the response body is fully sent. The request body size is read
+    /// from the `Content-Length` header.
+    ///
+    /// Defaults to a meter of the global meter provider set at the time of the first
+    /// request, with the crate name as instrumentation scope.
+    ///
+    /// # Arguments
+    ///
+    /// * `meter` - Meter creating the HTTP server instruments
+    pub fn meter(self, meter: &Meter) -> Self {
+        OtelHttpServerLayer {
+            metrics: ServerMetrics::new(meter),
+            ..self
+        }
+    }
+}
+
+impl<S, R, F, OnReq, OnRes> Layer<S> for OtelHttpServerLayer<R, F, OnReq, OnRes>
+where
+    R: Clone,
+    F: Clone,
+    OnReq: Clone,
+    OnRes: Clone,
+{
+    /// The wrapped service
+    type Service = OtelHttpServerService<S, R, F, OnReq, OnRes>;
+    fn layer(&self, inner: S) -> Self::Service {
+        OtelHttpServerService {
+            inner,
+            route: self.route.clone(),
+            filter: self.filter.clone(),
+            on_request: self.on_request.clone(),
+            on_response: self.on_response.clone(),
+            inject_context: self.inject_context,
+            debug_trigger: self.debug_trigger.clone(),
+            context_trust: self.context_trust.clone(),
+            peer_address: self.peer_address,
+            trusted_proxies: self.trusted_proxies.clone(),
+            trace_response_headers: self.trace_response_headers.clone(),
+            request_id: self.request_id.clone(),
+            header_capture: self.header_capture.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+/// OpenTelemetry service wrapper for HTTP servers.
+///
+/// This service wraps HTTP services to provide automatic HTTP request tracing
+/// with OpenTelemetry spans and context propagation.
+#[derive(Debug, Clone)]
+pub struct OtelHttpServerService<S, R = (), F = (), OnReq = (), OnRes = ()> {
+    inner: S,
+    route: R,
+    filter: F,
+    on_request: OnReq,
+    on_response: OnRes,
+    inject_context: bool,
+    debug_trigger: Option<DebugTrigger>,
+    context_trust: ContextTrust,
+    peer_address: Option<PeerAddress>,
+    trusted_proxies: TrustedProxies,
+    trace_response_headers: Vec<TraceResponseHeader>,
+    request_id: Option<RequestIdHeader>,
+    header_capture: HeaderCapture,
+    metrics: ServerMetrics,
+}
+
+impl<S, B, B2, R, F, OnReq, OnRes> Service<Request<B>>
+    for OtelHttpServerService<S, R, F, OnReq, OnRes>
+where
+    S: Service<Request<B>, Response = Response<B2>> + Clone + Send + 'static,
+    S::Error: Error + 'static, //fmt::Display + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+    R: RouteExtractor<B>,
+    F: RequestFilter<B>,
+    OnReq: OnRequest<B>,
+    B2: http_body::Body,
+    OnRes: OnResponse<B2> + Clone,
+{
+    type Response = Response<ResponseBody<B2>>;
+    type Error = S::Error;
+    // #[allow(clippy::type_complexity)]
+    // type Future = futures_core::future::BoxFuture<'static, Result<Self::Response, Self::Error>>;
+    type Future = ResponseFuture<S::Future, OnRes>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let started = Instant::now();
+        let expects_body = req.method() != Method::HEAD;
+        let mut request_metrics = None;
+        let mut on_response = None;
+        let mut request_id = None;
+        let span = if self.filter.is_traced(&req) {
+            let span = otel_http::http_server::make_span_from_request(&req);
+            let route = self.route.route(&req).unwrap_or_default();
+            request_metrics = Some(self.metrics.start(&req, &route));
+            let method = req.method();
+            span.record("http.route", route.as_str());
+            span.record("otel.name", format!("{method} {route}").trim());
+            // span.record("trace_id", find_trace_id_from_tracing(&span));
+            let peer_address = self
+                .peer_address
+                .and_then(|peer_address| peer_address(req.extensions()));
+            if let Some(peer_address) = peer_address {
+                span.set_attribute(
+                    semconv::NETWORK_PEER_ADDRESS,
+                    peer_address.ip().to_string(),
+                );
+                span.set_attribute(
+                    semconv::NETWORK_PEER_PORT,
+                    i64::from(peer_address.port()),
+                );
+            }
+            if let Some((ip, port)) = self
+                .trusted_proxies
+                .client_address(req.headers(), peer_address)
+            {
+                span.set_attribute(semconv::CLIENT_ADDRESS, ip.to_string());
+                if let Some(port) = port {
+                    span.set_attribute(semconv::CLIENT_PORT, i64::from(port));
+                }
+            }
+            for attribute in self.header_capture.request_attributes(req.headers()) {
+                span.set_attribute(attribute.key, attribute.value);
+            }
+            let incoming_context = otel_http::extract_context(req.headers());
+            if let Some(propagator) = crate::propagation::extracted_by(&incoming_context)
+            {
+                span.set_attribute(EXTRACTED_BY_ATTRIBUTE, propagator.to_owned());
+            }
+            let peer_ip = peer_address.map(|address| address.ip());
+            let parent_context = if self.context_trust.is_trusted(req.headers(), peer_ip)
+            {
+                incoming_context
+            } else {
+                // start a new trace, keeping the incoming one as a link
+                span.add_link(incoming_context.span().span_context().clone());
+                opentelemetry::Context::new()
+            };
+            let request_id_cell = crate::http::RequestIdCell::default();
+            let span_parent_context = match &self.request_id {
+                Some(_) => parent_context.with_value(request_id_cell.clone()),
+                None => parent_context.clone(),
+            };
+            if let Err(err) = span.set_parent(span_parent_context) {
+                tracing::warn!(?err, "span context cannot be set");
+            };
+            if let Some(header) = &self.request_id {
+                let id = header.inbound(req.headers()).unwrap_or_else(|| {
+                    header.generate(|| span.context().span().span_context().trace_id())
+                });
+                span.set_attribute(REQUEST_ID_ATTRIBUTE, id.as_str().to_owned());
+                request_id_cell.set(id.clone());
+                request_id = Some(id);
+            }
+            if let Some(trigger) = &self.debug_trigger
+                && trigger.matches(req.headers(), &parent_context)
+            {
+                enable_debug_logging(&span);
+            }
+            self.on_request.on_request(&req, &span);
+            on_response = Some(self.on_response.clone());
+            span
+        } else {
+            request_id = self.request_id.as_ref().map(|header| {
+                header
+                    .inbound(req.headers())
+                    .unwrap_or_else(|| header.generate(|| TraceId::INVALID))
+            });
+            tracing::Span::none()
+        };
+        if let Some(request_id) = &request_id {
+            req.extensions_mut().insert(request_id.clone());
+        }
+        let future = {
+            let _guard = span.enter();
+            self.inner.call(req)
+        };
+        ResponseFuture {
+            inner: future,
+            inject_context: self.inject_context,
+            trace_response_headers: self.trace_response_headers.clone(),
+            request_id,
+            header_capture: self.header_capture.clone(),
+            request_metrics,
+            on_response,
+            started,
+            expects_body,
+            span,
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`OtelHttpServerService`].
+    pub struct ResponseFuture<F, OnRes = ()> {
+        #[pin]
+        pub(crate) inner: F,
+        pub(crate) inject_context: bool,
+        pub(crate) trace_response_headers: Vec<TraceResponseHeader>,
+        pub(crate) request_id: Option<RequestId>,
+        pub(crate) header_capture: HeaderCapture,
+        pub(crate) request_metrics: Option<RequestMetrics>,
+        pub(crate) on_response: Option<OnRes>,
+        pub(crate) started: Instant,
+        pub(crate) expects_body: bool,
+        pub(crate) span: Span,
+    }
+}
+
+impl<Fut, ResBody, E, OnRes> Future for ResponseFuture<Fut, OnRes>
+where
+    Fut: Future<Output = Result<Response<ResBody>, E>>,
+    E: std::error::Error + 'static,
+    ResBody: http_body::Body,
+    OnRes: OnResponse<ResBody>,
+{
+    type Output = Result<Response<ResponseBody<ResBody>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+        let this = self.project();
+        let _guard = this.span.enter();
+        let poll = match crate::panic::record_panics(this.span, || this.inner.poll(cx)) {
+            Ok(poll) => poll,
+            Err(panic) => {
+                if let Some(mut request_metrics) = this.request_metrics.take() {
+                    request_metrics.set_error_type(crate::panic::PANIC_TYPE);
+                    request_metrics.end(None);
+                }
+                panic.resume();
+            }
+        };
+        let result = futures_util::ready!(poll);
+        otel_http::http_server::update_span_from_response_or_error(this.span, &result);
+        let mut response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                if let Some(mut request_metrics) = this.request_metrics.take() {
+                    request_metrics.set_error();
+                    request_metrics.end(None);
+                }
+                return Poll::Ready(Err(err));
+            }
+        };
+        for attribute in this.header_capture.response_attributes(response.headers()) {
+            this.span.set_attribute(attribute.key, attribute.value);
+        }
+        if let Some(on_response) = this.on_response.take() {
+            on_response.on_response(&response, this.span);
+        }
+        if *this.inject_context {
+            otel_http::inject_context(
+                &tracing_opentelemetry_instrumentation_sdk::find_current_context(),
+                response.headers_mut(),
+            );
+        }
+        if !this.trace_response_headers.is_empty() {
+            let context = this.span.context();
+            let span_ref = context.span();
+            for header in this.trace_response_headers.iter() {
+                header.insert(response.headers_mut(), span_ref.span_context());
+            }
+        }
+        if let Some(request_id) = this.request_id.take() {
+            request_id.insert(response.headers_mut());
+        }
+
+        // the span and the request metrics end with the response body
+        let response = match this.request_metrics.take() {
+            Some(mut request_metrics) => {
+                request_metrics.set_response(&response);
+                let status = response.status();
+                let expects_body = *this.expects_body
+                    && !status.is_informational()
+                    && status != http::StatusCode::NO_CONTENT
+                    && status != http::StatusCode::NOT_MODIFIED;
+                let span = this.span.clone();
+                let started = *this.started;
+                response.map(|body| {
+                    ResponseBody::traced(
+                        body,
+                        span,
+                        started,
+                        request_metrics,
+                        expects_body,
+                    )
+                })
+            }
+            None => response.map(ResponseBody::untraced),
+        };
+        Poll::Ready(Ok(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+    use rstest::rstest;
+    use std::convert::Infallible;
+    use tower::ServiceExt;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    async fn server_span<R>(layer: OtelHttpServerLayer<R>, req: Request<()>) -> SpanData
+    where
+        R: RouteExtractor<()> + Clone,
+    {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let service = layer.layer(tower::service_fn(|_: Request<()>| async {
+            Ok::<_, Infallible>(Response::new(String::new()))
+        }));
+        drop(service.oneshot(req).await.unwrap());
+
+        provider.force_flush().unwrap();
+        let mut spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        spans.remove(0)
+    }
+
+    #[rstest]
+    #[case("/users/42", "GET /users/{id}", "/users/{id}")]
+    #[case("/health", "GET", "")]
+    #[tokio::test]
+    async fn test_route_extractor(
+        #[case] path: &str,
+        #[case] span_name: &str,
+        #[case] route: &str,
+    ) {
+        let layer = OtelHttpServerLayer::new().route(|req: &Request<()>| {
+            let id = req.uri().path().strip_prefix("/users/")?;
+            (!id.is_empty()).then(|| "/users/{id}".to_owned())
+        });
+        let req = Request::builder().uri(path).body(()).unwrap();
+
+        let span = server_span(layer, req).await;
+        let http_route = span
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == semconv::HTTP_ROUTE)
+            .map(|kv| kv.value.to_string());
+
+        assert!(span.name == span_name);
+        assert!(http_route.as_deref() == Some(route));
+    }
+
+    #[tokio::test]
+    async fn test_without_route() {
+        let req = Request::builder().uri("/users/42").body(()).unwrap();
+
+        let span = server_span(OtelHttpServerLayer::new(), req).await;
+
+        assert!(span.name == "GET");
+    }
+
+    #[tokio::test]
+    async fn test_inner_call_in_server_span() {
+        let _guard = tracing::subscriber::set_default(Registry::default());
+        let current = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let service = OtelHttpServerLayer::new().layer(tower::service_fn({
+            let current = current.clone();
+            move |_: Request<()>| {
+                let span = tracing::Span::current();
+                *current.lock().unwrap() = span.metadata().map(|meta| meta.name());
+                async { Ok::<_, Infallible>(Response::new(String::new())) }
+            }
+        }));
+
+        drop(
+            service
+                .oneshot(Request::builder().uri("/").body(()).unwrap())
+                .await,
+        );
+
+        assert!(*current.lock().unwrap() == Some("HTTP request"));
+    }
+}
@@ -0,0 +1,133 @@
+use http::{HeaderMap, HeaderName};
+use opentelemetry::trace::TraceId;
+
+use crate::http::RequestId;
+
+const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+/// Maximum length of inbound request ids, longer ones are replaced.
+const MAX_INBOUND_LENGTH: usize = 256;
+
+/// Generator of the request id of requests without a valid request id header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RequestIdGenerator {
+    /// Random UUID v4, e.g. `67e55044-10b1-426f-9247-bb680e5fe0c8` (default).
+    #[default]
+    Uuid,
+    /// Hex encoded trace id of the server span, e.g. `4bf92f3577b34da6a3ce929d0e0e4736`.
+    ///
+    /// Falls back to a random UUID for requests which are not traced.
+    TraceId,
+}
+
+/// Header carrying the request id of requests and responses.
+///
+/// The request id of a request is read from the header, or generated if the header is
+/// missing, empty or longer than 256 characters. It is recorded as `request.id` on the
+/// server span, stored in the request extensions as a [`RequestId`] and echoed in the
+/// response header, see [`RequestId`] for its propagation.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::middleware::http_server::{RequestIdGenerator, RequestIdHeader};
+///
+/// let header = RequestIdHeader::x_request_id().generator(RequestIdGenerator::TraceId);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestIdHeader {
+    name: HeaderName,
+    generator: RequestIdGenerator,
+}
+
+impl RequestIdHeader {
+    /// Request ids carried by the given header.
+    pub fn new(name: HeaderName) -> Self {
+        Self {
+            name,
+            generator: RequestIdGenerator::default(),
+        }
+    }
+
+    /// Request ids carried by the `x-request-id` header.
+    pub fn x_request_id() -> Self {
+        Self::new(X_REQUEST_ID)
+    }
+
+    /// Sets the generator of missing request ids, random UUIDs by default.
+    pub fn generator(self, generator: RequestIdGenerator) -> Self {
+        Self { generator, ..self }
+    }
+
+    /// Returns the valid request id of the request headers, if any.
+    pub(crate) fn inbound(&self, headers: &HeaderMap) -> Option<RequestId> {
+        let value = headers.get(&self.name)?.to_str().ok()?;
+        if value.len() > MAX_INBOUND_LENGTH {
+            return None;
+        }
+        RequestId::new(self.name.clone(), value)
+    }
+
+    /// Generates a request id, reading the trace id of the server span if needed.
+    pub(crate) fn generate(&self, trace_id: impl FnOnce() -> TraceId) -> RequestId {
+        let value = match self.generator {
+            RequestIdGenerator::TraceId => Some(trace_id())
+                .filter(|trace_id| *trace_id != TraceId::INVALID)
+                .map(|trace_id| trace_id.to_string()),
+            RequestIdGenerator::Uuid => None,
+        };
+        let value = value.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        RequestId::new(self.name.clone(), value).expect("generated request ids are valid")
+    }
+}
+
+impl Default for RequestIdHeader {
+    fn default() -> Self {
+        Self::x_request_id()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    #[rstest]
+    #[case(Some("abc-123".to_owned()), Some("abc-123"))]
+    #[case(Some(String::new()), None)]
+    #[case(Some("a".repeat(257)), None)]
+    #[case(None, None)]
+    fn test_inbound(#[case] header: Option<String>, #[case] expected: Option<&str>) {
+        let mut headers = HeaderMap::new();
+        if let Some(header) = header {
+            headers.insert(X_REQUEST_ID, header.parse().unwrap());
+        }
+
+        let request_id = RequestIdHeader::x_request_id().inbound(&headers);
+
+        assert!(request_id.as_ref().map(RequestId::as_str) == expected);
+    }
+
+    #[rstest]
+    #[case(RequestIdGenerator::TraceId, TraceId::from_hex(TRACE_ID).unwrap(), Some(TRACE_ID))]
+    #[case(RequestIdGenerator::TraceId, TraceId::INVALID, None)]
+    #[case(RequestIdGenerator::Uuid, TraceId::from_hex(TRACE_ID).unwrap(), None)]
+    fn test_generate(
+        #[case] generator: RequestIdGenerator,
+        #[case] trace_id: TraceId,
+        #[case] expected: Option<&str>,
+    ) {
+        let header = RequestIdHeader::new(HeaderName::from_static("x-correlation-id"))
+            .generator(generator);
+
+        let request_id = header.generate(|| trace_id);
+
+        assert!(request_id.header_name() == "x-correlation-id");
+        match expected {
+            Some(expected) => assert!(request_id.as_str() == expected),
+            None => assert!(uuid::Uuid::parse_str(request_id.as_str()).is_ok()),
+        }
+    }
+}
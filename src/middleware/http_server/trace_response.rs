@@ -0,0 +1,96 @@
+use http::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::trace::{SpanContext, TraceFlags};
+
+const TRACE_RESPONSE_HEADER: HeaderName = HeaderName::from_static("traceresponse");
+const XRAY_TRACE_HEADER: HeaderName = HeaderName::from_static("x-amzn-trace-id");
+
+/// Response header reporting the trace of a request to the client.
+///
+/// Unlike [`OtelHttpServerLayer::inject_context`](super::OtelHttpServerLayer::inject_context), which
+/// writes the output of the configured propagators, these headers have a fixed format
+/// and only describe the server span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceResponseHeader {
+    /// W3C `traceresponse` header: `00-{trace-id}-{server-span-id}-{trace-flags}`.
+    TraceResponse,
+    /// AWS X-Ray `X-Amzn-Trace-Id` header with the trace id only: `Root=1-{time}-{id}`.
+    XRay,
+    /// Custom header with the hex encoded trace id only, e.g. `x-trace-id`.
+    TraceId(HeaderName),
+}
+
+impl TraceResponseHeader {
+    pub(crate) fn insert(&self, headers: &mut HeaderMap, span_context: &SpanContext) {
+        if !span_context.is_valid() {
+            return;
+        }
+
+        let trace_id = span_context.trace_id().to_string();
+        let (name, value) = match self {
+            Self::TraceResponse => {
+                let flags = span_context.trace_flags() & TraceFlags::SAMPLED;
+                let value = format!(
+                    "00-{trace_id}-{}-{:02x}",
+                    span_context.span_id(),
+                    flags.to_u8(),
+                );
+                (TRACE_RESPONSE_HEADER, value)
+            }
+            Self::XRay => {
+                let value = format!("Root=1-{}-{}", &trace_id[..8], &trace_id[8..]);
+                (XRAY_TRACE_HEADER, value)
+            }
+            Self::TraceId(name) => (name.clone(), trace_id),
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::{SpanId, TraceId, TraceState};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        TraceResponseHeader::TraceResponse,
+        "traceresponse",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+    )]
+    #[case(
+        TraceResponseHeader::XRay,
+        "x-amzn-trace-id",
+        "Root=1-4bf92f35-77b34da6a3ce929d0e0e4736"
+    )]
+    #[case(
+        TraceResponseHeader::TraceId(HeaderName::from_static("x-trace-id")),
+        "x-trace-id",
+        "4bf92f3577b34da6a3ce929d0e0e4736"
+    )]
+    fn test_trace_response_header(
+        #[case] header: TraceResponseHeader,
+        #[case] name: &str,
+        #[case] value: &str,
+    ) {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        let mut headers = HeaderMap::new();
+        header.insert(&mut headers, &span_context);
+
+        assert!(headers.len() == 1);
+        assert!(headers[name] == value);
+
+        let mut headers = HeaderMap::new();
+        header.insert(&mut headers, &SpanContext::empty_context());
+        assert!(headers.is_empty());
+    }
+}
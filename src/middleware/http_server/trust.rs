@@ -0,0 +1,135 @@
+use http::HeaderMap;
+use ipnet::IpNet;
+use std::{fmt, net::IpAddr, sync::Arc};
+
+type HeaderPredicate = Arc<dyn Fn(&HeaderMap) -> bool + Send + Sync>;
+
+/// Policy deciding whether the trace context of incoming requests is trusted.
+///
+/// The server span of a trusted request is a child of the incoming context. The server
+/// span of an untrusted request starts a new trace, and the incoming span context is
+/// recorded as a span link instead, so that clients cannot force sampling decisions or
+/// attach spans to arbitrary traces. Baggage of untrusted requests is ignored.
+///
+/// A request is trusted if the policy trusts all requests, if the peer address belongs
+/// to one of the trusted networks, or if its headers match the trusted header predicate.
+/// Networks are only checked if the peer address is available, see
+/// [`OtelHttpServerLayer::peer_address`](super::OtelHttpServerLayer::peer_address).
+///
+/// # Example
+///
+/// ```rust
+/// use http::HeaderMap;
+/// use telemetry_rust::middleware::http_server::{ContextTrust, IpNet};
+///
+/// let trust = ContextTrust::never()
+///     .trust_network("10.0.0.0/8".parse::<IpNet>()?)
+///     .trust_headers(|headers: &HeaderMap| headers.contains_key("x-internal-token"));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct ContextTrust {
+    always: bool,
+    networks: Vec<IpNet>,
+    headers: Option<HeaderPredicate>,
+}
+
+impl ContextTrust {
+    /// Trusts the context of all incoming requests.
+    ///
+    /// This is the default policy.
+    pub fn always() -> Self {
+        Self {
+            always: true,
+            networks: Vec::new(),
+            headers: None,
+        }
+    }
+
+    /// Trusts the context of incoming requests only if they match the configured networks
+    /// or header predicate, none by default.
+    pub fn never() -> Self {
+        Self {
+            always: false,
+            ..Self::always()
+        }
+    }
+
+    /// Trusts requests from peer addresses in the given network.
+    pub fn trust_network(mut self, network: IpNet) -> Self {
+        self.networks.push(network);
+        self
+    }
+
+    /// Trusts requests from peer addresses in any of the given networks.
+    pub fn trust_networks(mut self, networks: impl IntoIterator<Item = IpNet>) -> Self {
+        self.networks.extend(networks);
+        self
+    }
+
+    /// Trusts requests with headers matching the predicate.
+    pub fn trust_headers(
+        self,
+        predicate: impl Fn(&HeaderMap) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            headers: Some(Arc::new(predicate)),
+            ..self
+        }
+    }
+
+    /// Returns `true` if the context of a request is trusted.
+    pub fn is_trusted(&self, headers: &HeaderMap, peer_address: Option<IpAddr>) -> bool {
+        self.always
+            || peer_address.is_some_and(|address| {
+                self.networks
+                    .iter()
+                    .any(|network| network.contains(&address))
+            })
+            || self
+                .headers
+                .as_ref()
+                .is_some_and(|predicate| predicate(headers))
+    }
+}
+
+impl Default for ContextTrust {
+    fn default() -> Self {
+        Self::always()
+    }
+}
+
+impl fmt::Debug for ContextTrust {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextTrust")
+            .field("always", &self.always)
+            .field("networks", &self.networks)
+            .field("headers", &self.headers.as_ref().map(|_| "<predicate>"))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use http::HeaderValue;
+
+    #[test]
+    fn test_context_trust() {
+        let trust = ContextTrust::never()
+            .trust_networks(["10.0.0.0/8".parse().unwrap(), "fd00::/8".parse().unwrap()])
+            .trust_headers(|headers| headers.contains_key("x-internal"));
+        let mut headers = HeaderMap::new();
+
+        assert!(ContextTrust::always().is_trusted(&headers, None));
+        assert!(!ContextTrust::never().is_trusted(&headers, "10.0.0.1".parse().ok()));
+        assert!(trust.is_trusted(&headers, "10.1.2.3".parse().ok()));
+        assert!(trust.is_trusted(&headers, "fd12::1".parse().ok()));
+        assert!(!trust.is_trusted(&headers, "192.168.0.1".parse().ok()));
+        assert!(!trust.is_trusted(&headers, None));
+
+        headers.insert("x-internal", HeaderValue::from_static("1"));
+        assert!(trust.is_trusted(&headers, None));
+    }
+}
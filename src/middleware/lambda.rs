@@ -3,7 +3,7 @@
 //! This module provides instrumentation layer for AWS Lambda functions.
 
 use crate::{
-    future::{InstrumentedFuture, InstrumentedFutureContext},
+    future::{InstrumentedFuture, InstrumentedFutureContext, RecordPanic},
     semconv,
 };
 use lambda_runtime::LambdaInvocation;
@@ -18,6 +18,8 @@ use tracing_opentelemetry_instrumentation_sdk::TRACING_TARGET;
 ///
 /// This layer provides automatic tracing instrumentation for AWS Lambda functions,
 /// creating spans for each invocation with appropriate FaaS semantic attributes.
+/// Handler panics are recorded on the invocation span as `exception` events, and
+/// the tracer provider is flushed before the panic propagates.
 ///
 /// # Example
 ///
@@ -54,6 +56,27 @@ use tracing_opentelemetry_instrumentation_sdk::TRACING_TARGET;
 /// ```
 pub struct OtelLambdaLayer {
     provider: TracerProvider,
+    #[cfg(feature = "xray")]
+    xray_context: XrayContext,
+}
+
+/// Defines how the X-Ray trace header of an invocation relates to its span.
+///
+/// The header is read from the invocation context, or from the `_X_AMZN_TRACE_ID`
+/// environment variable set by the runtime. Note that Lambda sends a header with
+/// `Sampled=0` when active tracing is disabled, so an unsampled header is always used
+/// as a link, leaving the sampling decision of the invocation span to the sampler.
+#[cfg(feature = "xray")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum XrayContext {
+    /// The invocation span is a child of the X-Ray trace context if it is sampled, and
+    /// is linked to it otherwise (default).
+    #[default]
+    Parent,
+    /// The invocation span starts a new trace, linked to the X-Ray trace context.
+    Link,
+    /// The X-Ray trace header is ignored.
+    Ignore,
 }
 
 impl OtelLambdaLayer {
@@ -63,7 +86,18 @@ impl OtelLambdaLayer {
     ///
     /// * `provider` - The tracer provider to use for creating spans
     pub fn new(provider: TracerProvider) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            #[cfg(feature = "xray")]
+            xray_context: XrayContext::default(),
+        }
+    }
+
+    /// Sets how the X-Ray trace header of invocations is used, see [`XrayContext`].
+    #[cfg(feature = "xray")]
+    pub fn xray_context(mut self, xray_context: XrayContext) -> Self {
+        self.xray_context = xray_context;
+        self
     }
 }
 
@@ -75,15 +109,27 @@ impl<S> Layer<S> for OtelLambdaLayer {
             inner,
             provider: self.provider.clone(),
             coldstart: true,
+            #[cfg(feature = "xray")]
+            xray_context: self.xray_context,
         }
     }
 }
 
 impl<T> InstrumentedFutureContext<T> for TracerProvider {
+    const CATCH_PANICS: bool = true;
+
     fn on_result(self, _: &T) {
-        if let Err(err) = self.force_flush() {
-            tracing::warn!(?err, "failed to flush tracer provider");
-        }
+        flush(&self);
+    }
+
+    fn on_panic(self) {
+        flush(&self);
+    }
+}
+
+fn flush(provider: &TracerProvider) {
+    if let Err(err) = provider.force_flush() {
+        tracing::warn!(?err, "failed to flush tracer provider");
     }
 }
 
@@ -95,6 +141,8 @@ pub struct OtelLambdaService<S> {
     inner: S,
     provider: TracerProvider,
     coldstart: bool,
+    #[cfg(feature = "xray")]
+    xray_context: XrayContext,
 }
 
 impl<S> Drop for OtelLambdaService<S> {
@@ -103,13 +151,31 @@ impl<S> Drop for OtelLambdaService<S> {
     }
 }
 
+#[cfg(feature = "xray")]
+const XRAY_TRACE_ENV: &str = "_X_AMZN_TRACE_ID";
+
+/// Extracts the context of an X-Ray trace header, if it has a valid span context.
+#[cfg(feature = "xray")]
+fn xray_parent_context(header: &str) -> Option<opentelemetry::Context> {
+    use opentelemetry::{propagation::TextMapPropagator, trace::TraceContextExt};
+    use opentelemetry_aws::trace::XrayPropagator;
+
+    let carrier = std::collections::HashMap::from([(
+        "x-amzn-trace-id".to_owned(),
+        header.to_owned(),
+    )]);
+    let cx = XrayPropagator::new().extract(&carrier);
+    cx.span().span_context().is_valid().then_some(cx)
+}
+
 impl<S, R> Service<LambdaInvocation> for OtelLambdaService<S>
 where
     S: Service<LambdaInvocation, Response = R>,
 {
     type Response = R;
     type Error = S::Error;
-    type Future = InstrumentedFuture<Instrumented<S::Future>, TracerProvider>;
+    type Future =
+        InstrumentedFuture<Instrumented<RecordPanic<S::Future>>, TracerProvider>;
 
     fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
         self.inner.poll_ready(cx)
@@ -131,7 +197,114 @@ where
 
         self.coldstart = false;
 
-        let future = self.inner.call(req).instrument(span);
+        #[cfg(feature = "xray")]
+        if self.xray_context != XrayContext::Ignore
+            && let Some(cx) = req
+                .context
+                .xray_trace_id
+                .clone()
+                .or_else(|| crate::util::env_var(XRAY_TRACE_ENV))
+                .and_then(|header| xray_parent_context(&header))
+        {
+            use crate::OpenTelemetrySpanExt;
+            use opentelemetry::trace::TraceContextExt;
+            let span_context = cx.span().span_context().clone();
+            match self.xray_context {
+                XrayContext::Parent if span_context.is_sampled() => {
+                    let _ = span.set_parent(cx);
+                }
+                XrayContext::Parent | XrayContext::Link => span.add_link(span_context),
+                XrayContext::Ignore => {}
+            }
+        }
+
+        let future =
+            RecordPanic::new(self.inner.call(req), span.clone()).instrument(span);
         InstrumentedFuture::new(future, self.provider.clone())
     }
 }
+
+#[cfg(all(test, feature = "xray"))]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::{TraceContextExt, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SpanData};
+    use rstest::rstest;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    const XRAY_TRACE_ID: &str = "5759e988bd862e3fe1be46a994272793";
+
+    async fn invocation_span(xray_context: XrayContext, header: &str) -> SpanData {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let inner = tower::service_fn(|_: LambdaInvocation| async {
+            Ok::<_, std::convert::Infallible>(())
+        });
+        let mut service = OtelLambdaLayer::new(provider.clone())
+            .xray_context(xray_context)
+            .layer(inner);
+        let mut context = lambda_runtime::Context::default();
+        context.xray_trace_id = Some(header.to_owned());
+        let req = LambdaInvocation {
+            parts: http::Response::new(()).into_parts().0,
+            body: Default::default(),
+            context,
+        };
+        service.call(req).await.unwrap();
+
+        let mut spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        spans.remove(0)
+    }
+
+    #[rstest]
+    #[case(XrayContext::Parent, "Sampled=1", true)]
+    #[case(XrayContext::Parent, "Sampled=0", false)]
+    #[case(XrayContext::Link, "Sampled=1", false)]
+    #[tokio::test]
+    async fn test_xray_context(
+        #[case] xray_context: XrayContext,
+        #[case] sampled: &str,
+        #[case] parent: bool,
+    ) {
+        let header = format!(
+            "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;{sampled}"
+        );
+        let span = invocation_span(xray_context, &header).await;
+        let xray_trace_id = TraceId::from_hex(XRAY_TRACE_ID).unwrap();
+
+        assert!(span.span_context.is_sampled());
+        assert!((span.span_context.trace_id() == xray_trace_id) == parent);
+        assert!(span.links.is_empty() == parent);
+    }
+
+    #[rstest]
+    #[case(
+        "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1",
+        Some(true)
+    )]
+    #[case(
+        "Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=0;Lineage=a87bd80c:0",
+        Some(false)
+    )]
+    #[case("Root=1-5759e988-bd862e3fe1be46a994272793", None)]
+    #[case("invalid", None)]
+    fn test_xray_parent_context(#[case] header: &str, #[case] sampled: Option<bool>) {
+        let cx = xray_parent_context(header);
+        let span_context = cx.as_ref().map(|cx| cx.span().span_context().clone());
+
+        assert!(span_context.as_ref().map(|sc| sc.is_sampled()) == sampled);
+        if let Some(span_context) = span_context {
+            assert!(span_context.trace_id() == TraceId::from_hex(XRAY_TRACE_ID).unwrap());
+            assert!(span_context.is_remote());
+        }
+    }
+}
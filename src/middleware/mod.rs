@@ -6,5 +6,11 @@ pub mod aws;
 #[cfg(feature = "axum")]
 pub mod axum;
 
+#[cfg(feature = "http-server")]
+pub mod http_server;
+
 #[cfg(feature = "aws-lambda")]
 pub mod lambda;
+
+#[cfg(feature = "tonic")]
+pub mod tonic;
@@ -0,0 +1,287 @@
+use bytes::Bytes;
+use http::HeaderMap;
+use http_body::{Body, Frame, SizeHint};
+use opentelemetry::{KeyValue, trace::SpanKind};
+use pin_project_lite::pin_project;
+use std::{
+    fmt::Display,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tonic::Code;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use super::{
+    RPC_MESSAGE_COMPRESSED_SIZE, RPC_MESSAGE_EVENT, RPC_MESSAGE_ID, RPC_MESSAGE_TYPE,
+    RPC_MESSAGE_UNCOMPRESSED_SIZE, grpc_status, record_error, record_status,
+};
+
+/// Size of the prefix of each gRPC message: a compression flag and a 4 bytes length.
+const MESSAGE_PREFIX_SIZE: usize = 5;
+
+/// Direction of the messages of a body, recorded as `rpc.message.type`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MessageType {
+    Sent,
+    Received,
+}
+
+impl MessageType {
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageType::Sent => "SENT",
+            MessageType::Received => "RECEIVED",
+        }
+    }
+}
+
+pin_project! {
+    /// Body of a gRPC request or response instrumented by the tonic layers.
+    ///
+    /// Records each message of the body as an `rpc.message` span event. The response
+    /// body keeps the span of the call open until the response stream ends, recording
+    /// the gRPC status read from the trailers, or from the headers of trailers-only
+    /// responses. If the response body is dropped before it ends, the call is recorded
+    /// as `CANCELLED`.
+    pub struct GrpcBody<B> {
+        #[pin]
+        inner: B,
+        state: Option<BodyState>,
+    }
+
+    impl<B> PinnedDrop for GrpcBody<B> {
+        fn drop(this: Pin<&mut Self>) {
+            if let Some(state) = this.project().state.take() {
+                state.cancelled();
+            }
+        }
+    }
+}
+
+impl<B: Body> GrpcBody<B> {
+    /// Wraps a request body, recording its messages on the span.
+    pub(crate) fn request(inner: B, span: Span, message_type: MessageType) -> Self {
+        let state = (!inner.is_end_stream()).then(|| BodyState::new(span, message_type));
+        Self { inner, state }
+    }
+
+    /// Wraps a response body, ending the call once the response stream ends.
+    ///
+    /// `status` is the gRPC status read from the response headers, if any.
+    pub(crate) fn response(
+        inner: B,
+        span: Span,
+        kind: SpanKind,
+        status: Option<(Code, Option<String>)>,
+    ) -> Self {
+        let message_type = match kind {
+            SpanKind::Server => MessageType::Sent,
+            _ => MessageType::Received,
+        };
+        let mut state = BodyState::new(span, message_type);
+        state.call = Some(CallState { kind, status });
+        if inner.is_end_stream() {
+            state.end();
+            Self { inner, state: None }
+        } else {
+            Self {
+                inner,
+                state: Some(state),
+            }
+        }
+    }
+}
+
+impl<B> Body for GrpcBody<B>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Display,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let result = futures_util::ready!(this.inner.as_mut().poll_frame(cx));
+
+        match &result {
+            Some(Ok(frame)) => {
+                if let Some(state) = this.state.as_mut() {
+                    if let Some(data) = frame.data_ref() {
+                        state.data(data);
+                    }
+                    if let Some(trailers) = frame.trailers_ref() {
+                        state.trailers(trailers);
+                    }
+                }
+                if this.inner.is_end_stream()
+                    && let Some(state) = this.state.take()
+                {
+                    state.end();
+                }
+            }
+            Some(Err(err)) => {
+                if let Some(state) = this.state.take() {
+                    state.failed(err);
+                }
+            }
+            None => {
+                if let Some(state) = this.state.take() {
+                    state.end();
+                }
+            }
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Progress of an instrumented body.
+struct BodyState {
+    span: Span,
+    message_type: MessageType,
+    messages: MessageDecoder,
+    message_id: i64,
+    call: Option<CallState>,
+}
+
+/// Outcome of the call ended by a response body.
+struct CallState {
+    kind: SpanKind,
+    status: Option<(Code, Option<String>)>,
+}
+
+impl BodyState {
+    fn new(span: Span, message_type: MessageType) -> Self {
+        Self {
+            span,
+            message_type,
+            messages: MessageDecoder::default(),
+            message_id: 0,
+            call: None,
+        }
+    }
+
+    fn data(&mut self, data: &[u8]) {
+        let Self {
+            span,
+            message_type,
+            messages,
+            message_id,
+            ..
+        } = self;
+        messages.decode(data, |compressed, size| {
+            *message_id += 1;
+            let size_attribute = match compressed {
+                true => RPC_MESSAGE_COMPRESSED_SIZE,
+                false => RPC_MESSAGE_UNCOMPRESSED_SIZE,
+            };
+            span.add_event(
+                RPC_MESSAGE_EVENT,
+                vec![
+                    KeyValue::new(RPC_MESSAGE_TYPE, message_type.as_str()),
+                    KeyValue::new(RPC_MESSAGE_ID, *message_id),
+                    KeyValue::new(size_attribute, size as i64),
+                ],
+            );
+        });
+    }
+
+    fn trailers(&mut self, trailers: &HeaderMap) {
+        if let Some(call) = self.call.as_mut()
+            && let Some(status) = grpc_status(trailers)
+        {
+            call.status = Some(status);
+        }
+    }
+
+    fn end(self) {
+        if let Some(call) = self.call {
+            let (code, message) = call.status.unwrap_or((Code::Unknown, None));
+            record_status(&self.span, &call.kind, code, message);
+        }
+    }
+
+    fn failed(self, error: &impl Display) {
+        if self.call.is_some() {
+            record_error(&self.span, error);
+        }
+    }
+
+    fn cancelled(self) {
+        if let Some(call) = self.call {
+            record_status(&self.span, &call.kind, Code::Cancelled, None);
+        }
+    }
+}
+
+/// Decoder of the length-prefixed messages of a gRPC stream.
+#[derive(Debug, Default)]
+struct MessageDecoder {
+    prefix: [u8; MESSAGE_PREFIX_SIZE],
+    prefix_len: usize,
+    remaining: usize,
+}
+
+impl MessageDecoder {
+    /// Decodes a chunk of the stream, calling `on_message` with the compression flag
+    /// and the size of each message starting in the chunk.
+    fn decode(&mut self, mut data: &[u8], mut on_message: impl FnMut(bool, usize)) {
+        loop {
+            let skipped = self.remaining.min(data.len());
+            self.remaining -= skipped;
+            data = &data[skipped..];
+            if data.is_empty() {
+                return;
+            }
+
+            let read = (MESSAGE_PREFIX_SIZE - self.prefix_len).min(data.len());
+            self.prefix[self.prefix_len..self.prefix_len + read]
+                .copy_from_slice(&data[..read]);
+            self.prefix_len += read;
+            data = &data[read..];
+            if self.prefix_len == MESSAGE_PREFIX_SIZE {
+                let [flag, size @ ..] = self.prefix;
+                let size = u32::from_be_bytes(size) as usize;
+                on_message(flag == 1, size);
+                self.prefix_len = 0;
+                self.remaining = size;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+
+    #[test]
+    fn test_message_decoder() {
+        let mut stream = Vec::new();
+        stream.extend([0, 0, 0, 0, 3, 1, 2, 3]);
+        stream.extend([1, 0, 0, 0, 0]);
+        stream.extend([0, 0, 0, 0, 2, 4, 5]);
+        let mut decoder = MessageDecoder::default();
+        let mut messages = Vec::new();
+
+        for chunk in stream.chunks(3) {
+            decoder.decode(chunk, |compressed, size| messages.push((compressed, size)));
+        }
+
+        assert!(messages == [(false, 3), (true, 0), (false, 2)]);
+        assert!(decoder.remaining == 0 && decoder.prefix_len == 0);
+    }
+}
@@ -0,0 +1,152 @@
+use bytes::Bytes;
+use http::{Request, Response, Uri};
+use opentelemetry::trace::SpanKind;
+use std::{
+    fmt::Display,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_opentelemetry_instrumentation_sdk::http as otel_http;
+
+use super::{GrpcBody, ResponseFuture, body::MessageType, rpc_span};
+use crate::{
+    instrumentations::http::{
+        PropagationPolicy,
+        policy::{Destination, should_propagate},
+    },
+    semconv,
+};
+
+/// OpenTelemetry layer for tonic clients.
+///
+/// Creates a client span for each gRPC call, child of the current span, and injects
+/// its trace context into the request metadata with the global propagator, if the
+/// destination is trusted by the [`PropagationPolicy`]. The span is named after the
+/// fully-qualified method and ends with the response stream, recording the gRPC status
+/// as `rpc.response.status_code` and `rpc.grpc.status_code`. All status codes but `OK`
+/// mark the span as failed.
+///
+/// Generated tonic clients send requests with relative URIs (e.g. `/pkg.Service/Method`)
+/// to the channel, unless created `with_origin`. Set the [origin](Self::origin) of the
+/// channel to record `server.address` and `server.port`, and to let the
+/// [`PropagationPolicy`] match the destination of calls. Without a known host, calls
+/// match no allow rule and all deny rules.
+///
+/// # Example
+///
+/// ```rust
+/// use http::Uri;
+/// use telemetry_rust::middleware::tonic::OtelTonicClientLayer;
+///
+/// let origin = Uri::from_static("http://greeter.internal:50051");
+/// let layer = tower::ServiceBuilder::new().layer(OtelTonicClientLayer::new().origin(origin));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OtelTonicClientLayer {
+    origin: Option<Uri>,
+    propagation_policy: Option<Arc<PropagationPolicy>>,
+}
+
+impl OtelTonicClientLayer {
+    /// Creates a new OpenTelemetry layer for tonic clients.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the origin of the channel, i.e. the URI of its endpoint, or the origin
+    /// given to `with_origin` of the client.
+    ///
+    /// The scheme and authority of the origin are used for calls with relative URIs,
+    /// and its path is stripped from the path of calls before reading the gRPC method.
+    pub fn origin(self, origin: Uri) -> Self {
+        Self {
+            origin: Some(origin),
+            ..self
+        }
+    }
+
+    /// Sets the [`PropagationPolicy`] deciding whether trace context is injected into
+    /// calls, overriding the global policy.
+    pub fn propagation_policy(self, policy: impl Into<Arc<PropagationPolicy>>) -> Self {
+        Self {
+            propagation_policy: Some(policy.into()),
+            ..self
+        }
+    }
+}
+
+impl<S> Layer<S> for OtelTonicClientLayer {
+    type Service = OtelTonicClientService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OtelTonicClientService {
+            inner,
+            origin: self.origin.clone(),
+            propagation_policy: self.propagation_policy.clone(),
+        }
+    }
+}
+
+/// OpenTelemetry service wrapper for tonic clients, see [`OtelTonicClientLayer`].
+#[derive(Debug, Clone)]
+pub struct OtelTonicClientService<S> {
+    inner: S,
+    origin: Option<Uri>,
+    propagation_policy: Option<Arc<PropagationPolicy>>,
+}
+
+impl<S, ResBody> Service<Request<tonic::body::Body>> for OtelTonicClientService<S>
+where
+    S: Service<Request<tonic::body::Body>, Response = Response<ResBody>>,
+    S::Error: Display,
+    ResBody: http_body::Body<Data = Bytes>,
+{
+    type Response = Response<GrpcBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<tonic::body::Body>) -> Self::Future {
+        let uri = req.uri();
+        let server = match &self.origin {
+            Some(origin) if uri.authority().is_none() => origin,
+            _ => uri,
+        };
+        let path = self
+            .origin
+            .as_ref()
+            .and_then(|origin| {
+                uri.path().strip_prefix(origin.path().trim_end_matches('/'))
+            })
+            .filter(|path| path.starts_with('/'))
+            .unwrap_or(uri.path());
+        let span = rpc_span(path, SpanKind::Client);
+        if let Some(host) = server.host() {
+            span.set_attribute(semconv::SERVER_ADDRESS, host.to_owned());
+        }
+        if let Some(port) = server.port_u16() {
+            span.set_attribute(semconv::SERVER_PORT, i64::from(port));
+        }
+        let destination = Destination {
+            path: Some(uri.path()),
+            ..Destination::from_uri(server)
+        };
+        if should_propagate(self.propagation_policy.as_deref(), &destination) {
+            otel_http::inject_context(&span.context(), req.headers_mut());
+        }
+        let req = req.map(|body| {
+            let body = GrpcBody::request(body, span.clone(), MessageType::Sent);
+            tonic::body::Body::new(body)
+        });
+        let future = {
+            let _guard = span.enter();
+            self.inner.call(req)
+        };
+        ResponseFuture::new(future, span, SpanKind::Client)
+    }
+}
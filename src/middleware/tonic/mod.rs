@@ -0,0 +1,595 @@
+//! gRPC middleware for tonic servers and clients.
+//!
+//! Provides [`tower`] layers creating RPC spans following the OpenTelemetry semantic
+//! conventions for gRPC, propagating the trace context through request metadata.
+//! Spans stay open until the response stream ends, and record every streamed
+//! message as an `rpc.message` event.
+//!
+//! [`OtelTonicServerLayer`] is added to servers with `Server::builder().layer(...)`,
+//! and [`OtelTonicClientLayer`] wraps the channel of generated clients, e.g.
+//! `GreeterClient::new(ServiceBuilder::new().layer(layer).service(channel))`, where
+//! `layer` is `OtelTonicClientLayer::new().origin(uri)` with the URI of the channel
+//! endpoint.
+
+use http::{HeaderMap, Response, StatusCode};
+use opentelemetry::trace::{SpanKind, Status};
+use percent_encoding::percent_decode_str;
+use pin_project_lite::pin_project;
+use std::{
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tonic::Code;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::semconv;
+
+mod body;
+mod client;
+mod server;
+
+pub use body::GrpcBody;
+pub use client::{OtelTonicClientLayer, OtelTonicClientService};
+pub use server::{OtelTonicServerLayer, OtelTonicServerService};
+
+const RPC_SYSTEM_NAME: &str = "grpc";
+const OTHER_RPC_METHOD: &str = "_OTHER";
+const OTHER_ERROR_TYPE: &str = "_OTHER";
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+const GRPC_MESSAGE_HEADER: &str = "grpc-message";
+
+// Attributes deprecated by the semantic conventions, still recorded as they are
+// expected by most gRPC dashboards and backends.
+/// Numeric gRPC status code of the call.
+const RPC_GRPC_STATUS_CODE: &str = "rpc.grpc.status_code";
+/// Span event recorded for each message sent or received in a call.
+const RPC_MESSAGE_EVENT: &str = "rpc.message";
+const RPC_MESSAGE_TYPE: &str = "rpc.message.type";
+const RPC_MESSAGE_ID: &str = "rpc.message.id";
+const RPC_MESSAGE_COMPRESSED_SIZE: &str = "rpc.message.compressed_size";
+const RPC_MESSAGE_UNCOMPRESSED_SIZE: &str = "rpc.message.uncompressed_size";
+
+pin_project! {
+    /// Response future of the tonic layers.
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        span: Span,
+        kind: SpanKind,
+    }
+}
+
+impl<F> ResponseFuture<F> {
+    fn new(inner: F, span: Span, kind: SpanKind) -> Self {
+        Self { inner, span, kind }
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+    B: http_body::Body,
+    E: Display,
+{
+    type Output = Result<Response<GrpcBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let _guard = this.span.enter();
+        let result = futures_util::ready!(this.inner.poll(cx));
+        let response = match result {
+            Ok(response) => response,
+            Err(err) => {
+                record_error(this.span, &err);
+                return Poll::Ready(Err(err));
+            }
+        };
+        let status = grpc_status(response.headers()).or_else(|| {
+            let http_status = response.status();
+            (http_status != StatusCode::OK)
+                .then(|| (code_from_http_status(http_status), None))
+        });
+        let span = this.span.clone();
+        let kind = this.kind.clone();
+        Poll::Ready(Ok(
+            response.map(|body| GrpcBody::response(body, span, kind, status))
+        ))
+    }
+}
+
+/// Creates the span of a gRPC call, named after the fully-qualified method.
+fn rpc_span(path: &str, kind: SpanKind) -> Span {
+    let method = rpc_method(path);
+    let span = tracing::trace_span!(
+        target: tracing_opentelemetry_instrumentation_sdk::TRACING_TARGET,
+        "gRPC call",
+        "otel.kind" = ?kind,
+        "otel.name" = method.unwrap_or(OTHER_RPC_METHOD),
+        { semconv::RPC_SYSTEM_NAME } = RPC_SYSTEM_NAME,
+        { semconv::RPC_METHOD } = method.unwrap_or(OTHER_RPC_METHOD),
+    );
+    if method.is_none() {
+        span.set_attribute(semconv::RPC_METHOD_ORIGINAL, path.to_owned());
+    }
+    span
+}
+
+/// Returns the fully-qualified method of a gRPC request path, e.g.
+/// `helloworld.Greeter/SayHello` for `/helloworld.Greeter/SayHello`.
+fn rpc_method(path: &str) -> Option<&str> {
+    let method = path.strip_prefix('/')?;
+    let (service, name) = method.split_once('/')?;
+    (!service.is_empty() && !name.is_empty() && !name.contains('/')).then_some(method)
+}
+
+/// Reads the gRPC status of a call from response headers or trailers.
+fn grpc_status(headers: &HeaderMap) -> Option<(Code, Option<String>)> {
+    let code = Code::from_bytes(headers.get(GRPC_STATUS_HEADER)?.as_bytes());
+    let message = headers
+        .get(GRPC_MESSAGE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| percent_decode_str(value).decode_utf8_lossy().into_owned())
+        .filter(|message| !message.is_empty());
+    Some((code, message))
+}
+
+/// Maps the HTTP status of a response without gRPC status to a gRPC status code, see
+/// <https://github.com/grpc/grpc/blob/master/doc/http-grpc-status-mapping.md>.
+fn code_from_http_status(status: StatusCode) -> Code {
+    match status {
+        StatusCode::BAD_REQUEST => Code::Internal,
+        StatusCode::UNAUTHORIZED => Code::Unauthenticated,
+        StatusCode::FORBIDDEN => Code::PermissionDenied,
+        StatusCode::NOT_FOUND => Code::Unimplemented,
+        StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => Code::Unavailable,
+        _ => Code::Unknown,
+    }
+}
+
+/// Name of a gRPC status code as recorded in `rpc.response.status_code`.
+fn code_name(code: Code) -> &'static str {
+    match code {
+        Code::Ok => "OK",
+        Code::Cancelled => "CANCELLED",
+        Code::Unknown => "UNKNOWN",
+        Code::InvalidArgument => "INVALID_ARGUMENT",
+        Code::DeadlineExceeded => "DEADLINE_EXCEEDED",
+        Code::NotFound => "NOT_FOUND",
+        Code::AlreadyExists => "ALREADY_EXISTS",
+        Code::PermissionDenied => "PERMISSION_DENIED",
+        Code::ResourceExhausted => "RESOURCE_EXHAUSTED",
+        Code::FailedPrecondition => "FAILED_PRECONDITION",
+        Code::Aborted => "ABORTED",
+        Code::OutOfRange => "OUT_OF_RANGE",
+        Code::Unimplemented => "UNIMPLEMENTED",
+        Code::Internal => "INTERNAL",
+        Code::Unavailable => "UNAVAILABLE",
+        Code::DataLoss => "DATA_LOSS",
+        Code::Unauthenticated => "UNAUTHENTICATED",
+    }
+}
+
+/// Returns `true` if the status code marks the span as failed.
+///
+/// Clients treat all codes but `OK` as errors, while servers only treat codes
+/// reporting a server failure as errors, see
+/// <https://opentelemetry.io/docs/specs/semconv/rpc/grpc/#grpc-status>.
+fn is_error(code: Code, kind: &SpanKind) -> bool {
+    match kind {
+        SpanKind::Server => matches!(
+            code,
+            Code::Unknown
+                | Code::DeadlineExceeded
+                | Code::Unimplemented
+                | Code::Internal
+                | Code::Unavailable
+                | Code::DataLoss
+        ),
+        _ => code != Code::Ok,
+    }
+}
+
+/// Records the gRPC status of a finished call on its span.
+fn record_status(span: &Span, kind: &SpanKind, code: Code, message: Option<String>) {
+    span.set_attribute(semconv::RPC_RESPONSE_STATUS_CODE, code_name(code));
+    span.set_attribute(RPC_GRPC_STATUS_CODE, code as i64);
+    if is_error(code, kind) {
+        span.set_attribute(semconv::ERROR_TYPE, code_name(code));
+        span.set_status(Status::error(message.unwrap_or_default()));
+    }
+}
+
+/// Records an error which aborted the call before it got a gRPC status.
+fn record_error(span: &Span, error: &impl Display) {
+    span.set_attribute(semconv::ERROR_TYPE, OTHER_ERROR_TYPE);
+    span.set_status(Status::error(error.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instrumentations::http::{
+        PropagationPolicy, policy::clear_global_propagation_policy,
+        set_global_propagation_policy,
+    };
+    use assert2::assert;
+    use bytes::Bytes;
+    use http::Request;
+    use http_body::Frame;
+    use http_body_util::{BodyExt, StreamBody};
+    use opentelemetry::trace::{SpanId, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator,
+        trace::{InMemorySpanExporter, SdkTracerProvider, SpanData},
+    };
+    use rstest::rstest;
+    use serial_test::serial;
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+    use tower::{Layer, ServiceExt};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const SPAN_ID: &str = "00f067aa0ba902b7";
+
+    type TestBody = StreamBody<
+        futures_util::stream::Iter<std::vec::IntoIter<Result<Frame<Bytes>, Infallible>>>,
+    >;
+
+    fn message(payload: &[u8]) -> Frame<Bytes> {
+        let mut data = vec![0];
+        data.extend((payload.len() as u32).to_be_bytes());
+        data.extend(payload);
+        Frame::data(data.into())
+    }
+
+    fn status_trailers(code: Code) -> Frame<Bytes> {
+        let mut trailers = HeaderMap::new();
+        trailers.insert(GRPC_STATUS_HEADER, (code as i32).into());
+        Frame::trailers(trailers)
+    }
+
+    fn grpc_body(frames: Vec<Frame<Bytes>>) -> TestBody {
+        StreamBody::new(futures_util::stream::iter(
+            frames.into_iter().map(Ok).collect::<Vec<_>>(),
+        ))
+    }
+
+    fn grpc_request(frames: Vec<Frame<Bytes>>) -> Request<tonic::body::Body> {
+        Request::builder()
+            .method("POST")
+            .uri("/helloworld.Greeter/SayHello")
+            .header("content-type", "application/grpc")
+            .header("traceparent", format!("00-{TRACE_ID}-{SPAN_ID}-01"))
+            .body(tonic::body::Body::new(grpc_body(frames)))
+            .unwrap()
+    }
+
+    fn span_attribute(span: &SpanData, key: &str) -> Option<opentelemetry::Value> {
+        span.attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.clone())
+    }
+
+    /// Calls a service returning `response` through the layer of the given kind,
+    /// returning the span of the call and the request headers sent to the service.
+    async fn call_span(
+        kind: SpanKind,
+        req: Request<tonic::body::Body>,
+        response: Response<TestBody>,
+    ) -> (SpanData, HeaderMap) {
+        call_span_with_client(kind, OtelTonicClientLayer::new(), req, response).await
+    }
+
+    async fn call_span_with_client(
+        kind: SpanKind,
+        client: OtelTonicClientLayer,
+        req: Request<tonic::body::Body>,
+        response: Response<TestBody>,
+    ) -> (SpanData, HeaderMap) {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let response = Arc::new(Mutex::new(Some(response)));
+        let headers = Arc::new(Mutex::new(HeaderMap::new()));
+        let request_headers = headers.clone();
+        let inner = tower::service_fn(move |req: Request<tonic::body::Body>| {
+            let response = response.lock().unwrap().take().unwrap();
+            let headers = headers.clone();
+            async move {
+                *headers.lock().unwrap() = req.headers().clone();
+                let _ = req.into_body().collect().await;
+                Ok::<_, Infallible>(response)
+            }
+        });
+        let response = match kind {
+            SpanKind::Server => {
+                OtelTonicServerLayer::new().layer(inner).oneshot(req).await
+            }
+            _ => client.layer(inner).oneshot(req).await,
+        };
+        let _ = response.unwrap().into_body().collect().await;
+
+        provider.force_flush().unwrap();
+        let mut spans = exporter.get_finished_spans().unwrap();
+        assert!(spans.len() == 1);
+        let request_headers = request_headers.lock().unwrap().clone();
+        (spans.remove(0), request_headers)
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_server_call() {
+        let req = grpc_request(vec![message(b"hello")]);
+        let response = Response::new(grpc_body(vec![
+            message(b"hi"),
+            message(b""),
+            status_trailers(Code::Ok),
+        ]));
+
+        let (span, _) = call_span(SpanKind::Server, req, response).await;
+        let messages = span
+            .events
+            .iter()
+            .filter(|event| event.name == RPC_MESSAGE_EVENT)
+            .map(|event| {
+                let attribute = |key: &str| {
+                    event
+                        .attributes
+                        .iter()
+                        .find(|kv| kv.key.as_str() == key)
+                        .map(|kv| kv.value.to_string())
+                        .unwrap()
+                };
+                (attribute(RPC_MESSAGE_TYPE), attribute(RPC_MESSAGE_ID))
+            })
+            .collect::<Vec<_>>();
+
+        assert!(span.name == "helloworld.Greeter/SayHello");
+        assert!(span.span_kind == SpanKind::Server);
+        assert!(span.span_context.trace_id() == TraceId::from_hex(TRACE_ID).unwrap());
+        assert!(span.parent_span_id == SpanId::from_hex(SPAN_ID).unwrap());
+        assert!(span_attribute(&span, semconv::RPC_SYSTEM_NAME) == Some("grpc".into()));
+        assert!(
+            span_attribute(&span, semconv::RPC_RESPONSE_STATUS_CODE) == Some("OK".into())
+        );
+        assert!(span_attribute(&span, RPC_GRPC_STATUS_CODE) == Some(0.into()));
+        assert!(span.status == Status::Unset);
+        assert!(
+            messages
+                == [
+                    ("RECEIVED".to_owned(), "1".to_owned()),
+                    ("SENT".to_owned(), "1".to_owned()),
+                    ("SENT".to_owned(), "2".to_owned()),
+                ]
+        );
+    }
+
+    #[rstest]
+    #[case(SpanKind::Server, Code::NotFound, false, false)]
+    #[case(SpanKind::Server, Code::Internal, false, true)]
+    #[case(SpanKind::Server, Code::Unavailable, true, true)]
+    #[case(SpanKind::Client, Code::NotFound, false, true)]
+    #[case(SpanKind::Client, Code::NotFound, true, true)]
+    #[case(SpanKind::Client, Code::Ok, false, false)]
+    #[tokio::test]
+    #[serial]
+    async fn test_status_mapping(
+        #[case] kind: SpanKind,
+        #[case] code: Code,
+        #[case] trailers_only: bool,
+        #[case] error: bool,
+    ) {
+        let req = grpc_request(vec![message(b"hello")]);
+        let response = if trailers_only {
+            let mut response = Response::new(grpc_body(vec![]));
+            response
+                .headers_mut()
+                .insert(GRPC_STATUS_HEADER, (code as i32).into());
+            response
+        } else {
+            Response::new(grpc_body(vec![message(b"hi"), status_trailers(code)]))
+        };
+
+        let (span, _) = call_span(kind, req, response).await;
+
+        assert!(
+            span_attribute(&span, semconv::RPC_RESPONSE_STATUS_CODE)
+                == Some(code_name(code).into())
+        );
+        assert!(
+            span_attribute(&span, RPC_GRPC_STATUS_CODE) == Some((code as i64).into())
+        );
+        assert!(matches!(span.status, Status::Error { .. }) == error);
+        assert!(span_attribute(&span, semconv::ERROR_TYPE).is_some() == error);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_client_injects_context() {
+        let req = grpc_request(vec![message(b"hello")]);
+        let response = Response::new(grpc_body(vec![status_trailers(Code::Ok)]));
+
+        let (span, headers) = call_span(SpanKind::Client, req, response).await;
+        let span_id = span.span_context.span_id();
+
+        assert!(span.span_kind == SpanKind::Client);
+        assert!(
+            headers["traceparent"]
+                == format!("00-{}-{span_id}-01", span.span_context.trace_id()).as_str()
+        );
+    }
+
+    fn client_request(uri: &str) -> Request<tonic::body::Body> {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/grpc")
+            .body(tonic::body::Body::new(grpc_body(vec![message(b"hello")])))
+            .unwrap()
+    }
+
+    async fn client_span(
+        client: OtelTonicClientLayer,
+        uri: &str,
+    ) -> (SpanData, HeaderMap) {
+        let response = Response::new(grpc_body(vec![status_trailers(Code::Ok)]));
+        call_span_with_client(SpanKind::Client, client, client_request(uri), response)
+            .await
+    }
+
+    #[rstest]
+    #[case(None, "/helloworld.Greeter/SayHello", None, None)]
+    #[case(
+        Some("http://api.internal:50051"),
+        "/helloworld.Greeter/SayHello",
+        Some("api.internal"),
+        Some(50051)
+    )]
+    #[case(
+        Some("http://api.internal/prefix/"),
+        "http://api.internal/prefix/helloworld.Greeter/SayHello",
+        Some("api.internal"),
+        None
+    )]
+    #[case(
+        None,
+        "http://api.internal:8080/helloworld.Greeter/SayHello",
+        Some("api.internal"),
+        Some(8080)
+    )]
+    #[tokio::test]
+    #[serial]
+    async fn test_client_origin(
+        #[case] origin: Option<&str>,
+        #[case] uri: &str,
+        #[case] address: Option<&str>,
+        #[case] port: Option<i64>,
+    ) {
+        let mut client = OtelTonicClientLayer::new();
+        if let Some(origin) = origin {
+            client = client.origin(origin.parse().unwrap());
+        }
+
+        let (span, headers) = client_span(client, uri).await;
+
+        assert!(span.name == "helloworld.Greeter/SayHello");
+        assert!(
+            span_attribute(&span, semconv::RPC_METHOD)
+                == Some("helloworld.Greeter/SayHello".into())
+        );
+        assert!(
+            span_attribute(&span, semconv::SERVER_ADDRESS)
+                == address.map(|address| address.to_owned().into())
+        );
+        assert!(span_attribute(&span, semconv::SERVER_PORT) == port.map(Into::into));
+        assert!(headers.contains_key("traceparent"));
+    }
+
+    #[rstest]
+    #[case(Some("http://api.internal"), "/helloworld.Greeter/SayHello", true)]
+    #[case(
+        Some("http://api.thirdparty.com"),
+        "/helloworld.Greeter/SayHello",
+        false
+    )]
+    #[case(None, "http://api.internal/helloworld.Greeter/SayHello", true)]
+    #[case(None, "http://api.thirdparty.com/helloworld.Greeter/SayHello", false)]
+    #[case(None, "/helloworld.Greeter/SayHello", false)]
+    #[tokio::test]
+    #[serial]
+    async fn test_client_propagation_policy(
+        #[case] origin: Option<&str>,
+        #[case] uri: &str,
+        #[case] injected: bool,
+        #[values(false, true)] global: bool,
+    ) {
+        let policy = PropagationPolicy::new().deny_host("*.thirdparty.com");
+        let mut client = OtelTonicClientLayer::new();
+        if let Some(origin) = origin {
+            client = client.origin(origin.parse().unwrap());
+        }
+        if global {
+            set_global_propagation_policy(policy);
+        } else {
+            client = client.propagation_policy(policy);
+        }
+
+        let (_, headers) = client_span(client, uri).await;
+        if global {
+            clear_global_propagation_policy();
+        }
+
+        assert!(headers.contains_key("traceparent") == injected);
+    }
+
+    #[rstest]
+    #[case("/helloworld.Greeter/SayHello", Some("helloworld.Greeter/SayHello"))]
+    #[case("/Greeter/SayHello", Some("Greeter/SayHello"))]
+    #[case("/helloworld.Greeter", None)]
+    #[case("/helloworld.Greeter/", None)]
+    #[case("/a/b/c", None)]
+    #[case("", None)]
+    fn test_rpc_method(#[case] path: &str, #[case] expected: Option<&str>) {
+        assert!(rpc_method(path) == expected);
+    }
+
+    #[rstest]
+    #[case(Code::Ok, false, false)]
+    #[case(Code::NotFound, false, true)]
+    #[case(Code::InvalidArgument, false, true)]
+    #[case(Code::Cancelled, false, true)]
+    #[case(Code::Internal, true, true)]
+    #[case(Code::Unavailable, true, true)]
+    #[case(Code::DeadlineExceeded, true, true)]
+    fn test_is_error(
+        #[case] code: Code,
+        #[case] server_error: bool,
+        #[case] client_error: bool,
+    ) {
+        assert!(is_error(code, &SpanKind::Server) == server_error);
+        assert!(is_error(code, &SpanKind::Client) == client_error);
+    }
+
+    #[test]
+    fn test_grpc_status() {
+        let mut headers = HeaderMap::new();
+        headers.insert(GRPC_STATUS_HEADER, "5".parse().unwrap());
+        headers.insert(GRPC_MESSAGE_HEADER, "user%20not%20found".parse().unwrap());
+
+        let status = grpc_status(&headers);
+
+        assert!(status == Some((Code::NotFound, Some("user not found".to_owned()))));
+    }
+
+    #[test]
+    fn test_client_is_grpc_service() {
+        fn assert_grpc_service<S>(_: &S)
+        where
+            S: tonic::client::GrpcService<tonic::body::Body>,
+        {
+        }
+
+        let channel = tower::service_fn(|_: Request<tonic::body::Body>| async {
+            Ok::<_, Infallible>(Response::new(tonic::body::Body::empty()))
+        });
+
+        assert_grpc_service(&OtelTonicClientLayer::new().layer(channel));
+    }
+}
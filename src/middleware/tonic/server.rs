@@ -0,0 +1,85 @@
+use bytes::Bytes;
+use http::{Request, Response};
+use opentelemetry::trace::SpanKind;
+use std::{
+    fmt::Display,
+    task::{Context, Poll},
+};
+use tower::{Layer, Service};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_opentelemetry_instrumentation_sdk::http as otel_http;
+
+use super::{GrpcBody, ResponseFuture, body::MessageType, rpc_span};
+
+/// OpenTelemetry layer for tonic servers.
+///
+/// Creates a server span for each gRPC call, child of the trace context extracted
+/// from the request metadata with the global propagator. The span is named after the
+/// fully-qualified method and ends with the response stream, recording the gRPC
+/// status as `rpc.response.status_code` and `rpc.grpc.status_code`.
+///
+/// Only status codes reporting a server failure (`UNKNOWN`, `DEADLINE_EXCEEDED`,
+/// `UNIMPLEMENTED`, `INTERNAL`, `UNAVAILABLE` and `DATA_LOSS`) mark the span as failed.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::middleware::tonic::OtelTonicServerLayer;
+///
+/// let layer = tower::ServiceBuilder::new().layer(OtelTonicServerLayer::new());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OtelTonicServerLayer {}
+
+impl OtelTonicServerLayer {
+    /// Creates a new OpenTelemetry layer for tonic servers.
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl<S> Layer<S> for OtelTonicServerLayer {
+    type Service = OtelTonicServerService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OtelTonicServerService { inner }
+    }
+}
+
+/// OpenTelemetry service wrapper for tonic servers, see [`OtelTonicServerLayer`].
+#[derive(Debug, Clone)]
+pub struct OtelTonicServerService<S> {
+    inner: S,
+}
+
+impl<S, ResBody> Service<Request<tonic::body::Body>> for OtelTonicServerService<S>
+where
+    S: Service<Request<tonic::body::Body>, Response = Response<ResBody>>,
+    S::Error: Display,
+    ResBody: http_body::Body<Data = Bytes>,
+{
+    type Response = Response<GrpcBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<tonic::body::Body>) -> Self::Future {
+        let span = rpc_span(req.uri().path(), SpanKind::Server);
+        let parent_context = otel_http::extract_context(req.headers());
+        if let Err(err) = span.set_parent(parent_context) {
+            tracing::warn!(?err, "span context cannot be set");
+        }
+        let req = req.map(|body| {
+            let body = GrpcBody::request(body, span.clone(), MessageType::Received);
+            tonic::body::Body::new(body)
+        });
+        let future = {
+            let _guard = span.enter();
+            self.inner.call(req)
+        };
+        ResponseFuture::new(future, span, SpanKind::Server)
+    }
+}
@@ -0,0 +1,230 @@
+//! Panic capture for instrumented services.
+//!
+//! Instrumented services record the panics propagated by the inner service on their
+//! span as `exception` events, with the message of the panic. The panic hook installed
+//! by [`install_hook`] also captures the backtrace of panics, and panics caught before
+//! they reach the instrumented service, e.g. by the `catch_unwind` of an inner service.
+
+use opentelemetry::{KeyValue, trace::Status};
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe, PanicHookInfo},
+    sync::Once,
+};
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::semconv;
+
+/// Name of the span event recording a panic.
+const EXCEPTION_EVENT: &str = "exception";
+/// Exception and error type recorded for panics.
+pub(crate) const PANIC_TYPE: &str = "panic";
+
+thread_local! {
+    /// Number of [`record_panics`] calls in progress on the thread.
+    static RECORDING: Cell<usize> = const { Cell::new(0) };
+    /// Last panic raised on the thread while recording.
+    static LAST_PANIC: RefCell<Option<PanicRecord>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+/// A panic propagated out of [`record_panics`], to be resumed once handled.
+pub(crate) struct Panic(Box<dyn Any + Send>);
+
+impl Panic {
+    /// Resumes unwinding the panic.
+    pub(crate) fn resume(self) -> ! {
+        panic::resume_unwind(self.0)
+    }
+}
+
+/// Message and backtrace of a panic, captured by the panic hook.
+struct PanicRecord {
+    message: String,
+    backtrace: Option<Backtrace>,
+}
+
+impl PanicRecord {
+    fn from_payload(payload: &(dyn Any + Send)) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            (*message).to_owned()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_owned()
+        };
+        Self {
+            message,
+            backtrace: None,
+        }
+    }
+
+    /// Records the panic on the span as an `exception` event, marking the span as failed.
+    fn record(self, span: &Span) {
+        let mut attributes = vec![
+            KeyValue::new(semconv::EXCEPTION_MESSAGE, self.message.clone()),
+            KeyValue::new(semconv::EXCEPTION_TYPE, PANIC_TYPE),
+        ];
+        if let Some(backtrace) = self.backtrace {
+            attributes.push(KeyValue::new(
+                semconv::EXCEPTION_STACKTRACE,
+                backtrace.to_string(),
+            ));
+        }
+        span.add_event(EXCEPTION_EVENT, attributes);
+        span.set_attribute(semconv::ERROR_TYPE, PANIC_TYPE);
+        span.set_status(Status::error(self.message));
+    }
+}
+
+/// Invokes a closure, recording the panics propagated by it on the span, and the
+/// panics caught inside it if the hook is installed, see [`install_hook`].
+///
+/// Returns the panic propagated out of the closure, if any, to be resumed by the
+/// caller once it has handled it.
+pub(crate) fn record_panics<T>(span: &Span, f: impl FnOnce() -> T) -> Result<T, Panic> {
+    RECORDING.with(|recording| recording.set(recording.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    RECORDING.with(|recording| recording.set(recording.get() - 1));
+
+    let record = LAST_PANIC.with(|last_panic| last_panic.borrow_mut().take());
+    match result {
+        Ok(value) => {
+            if let Some(record) = record {
+                record.record(span);
+            }
+            Ok(value)
+        }
+        Err(payload) => {
+            let record = record.unwrap_or_else(|| PanicRecord::from_payload(&*payload));
+            record.record(span);
+            Err(Panic(payload))
+        }
+    }
+}
+
+/// Installs a panic hook capturing the panics raised while instrumented services
+/// handle requests, chaining the previously installed hook.
+///
+/// With the hook, recorded panics include their backtrace, which is captured for every
+/// panic raised on a thread while a service handles a request. Panics caught inside the
+/// service, e.g. by `tower_http::catch_panic`, are recorded on its span as well.
+/// Installing the hook more than once has no effect.
+///
+/// # Example
+///
+/// ```rust
+/// telemetry_rust::panic::install_hook();
+/// ```
+pub fn install_hook() {
+    // hooks cannot be changed while the thread is panicking
+    if std::thread::panicking() {
+        return;
+    }
+    PANIC_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info: &PanicHookInfo<'_>| {
+            if RECORDING.with(Cell::get) > 0 {
+                let record = PanicRecord {
+                    backtrace: Some(Backtrace::force_capture()),
+                    ..PanicRecord::from_payload(info.payload())
+                };
+                LAST_PANIC.with(|last_panic| *last_panic.borrow_mut() = Some(record));
+            }
+            previous_hook(info);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::{Registry, layer::SubscriberExt};
+
+    fn recorded_span<T>(f: impl FnOnce() -> T) -> (SpanData, Result<T, Panic>) {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber =
+            Registry::default().with(OpenTelemetryLayer::new(provider.tracer("test")));
+        let result = tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("handler");
+            record_panics(&span, f)
+        });
+        provider.force_flush().unwrap();
+        (exporter.get_finished_spans().unwrap().remove(0), result)
+    }
+
+    fn exception_attribute(span: &SpanData, key: &str) -> Option<String> {
+        let event = span
+            .events
+            .iter()
+            .find(|event| event.name == EXCEPTION_EVENT)?;
+        event
+            .attributes
+            .iter()
+            .find(|kv| kv.key.as_str() == key)
+            .map(|kv| kv.value.to_string())
+    }
+
+    #[test]
+    fn test_no_panic() {
+        let (span, result) = recorded_span(|| 42);
+
+        assert!(let Ok(42) = result);
+        assert!(span.events.is_empty());
+        assert!(span.status == Status::Unset);
+    }
+
+    #[test]
+    fn test_propagated_panic() {
+        let (span, result) = recorded_span(|| panic!("handler failed"));
+
+        assert!(let Err(Panic(_)) = result);
+        assert!(
+            exception_attribute(&span, semconv::EXCEPTION_MESSAGE)
+                == Some("handler failed".to_owned())
+        );
+        assert!(
+            exception_attribute(&span, semconv::EXCEPTION_TYPE)
+                == Some("panic".to_owned())
+        );
+        assert!(
+            span.attributes
+                .iter()
+                .any(|kv| kv.key.as_str() == semconv::ERROR_TYPE
+                    && kv.value.as_str() == "panic")
+        );
+        assert!(span.status == Status::error("handler failed"));
+    }
+
+    #[test]
+    fn test_caught_panic() {
+        install_hook();
+        let (span, result) = recorded_span(|| {
+            let _ = panic::catch_unwind(|| panic!("handler failed: {}", 42));
+        });
+
+        assert!(result.is_ok());
+
+        assert!(
+            exception_attribute(&span, semconv::EXCEPTION_MESSAGE)
+                == Some("handler failed: 42".to_owned())
+        );
+        assert!(
+            exception_attribute(&span, semconv::EXCEPTION_TYPE)
+                == Some("panic".to_owned())
+        );
+        assert!(exception_attribute(&span, semconv::EXCEPTION_STACKTRACE).is_some());
+        assert!(span.status == Status::error("handler failed: 42"));
+    }
+}
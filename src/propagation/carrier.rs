@@ -0,0 +1,305 @@
+use opentelemetry::{
+    Context,
+    propagation::{Extractor, Injector},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Maximum number of message attributes accepted by SQS and SNS.
+#[cfg(any(feature = "aws-sqs", feature = "aws-sns"))]
+const MAX_MESSAGE_ATTRIBUTES: usize = 10;
+
+/// Injector for string maps, e.g. message headers of queues and event buses.
+///
+/// Keys are inserted lowercase. Works with `HashMap<String, String>`,
+/// `BTreeMap<String, String>` and JSON objects (`serde_json::Map`).
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::BTreeMap;
+/// use telemetry_rust::propagation::{MapInjector, inject_context_into};
+///
+/// let mut headers = BTreeMap::new();
+/// inject_context_into(&mut MapInjector(&mut headers));
+/// ```
+pub struct MapInjector<'a, M>(pub &'a mut M);
+
+/// Extractor for string maps, e.g. Lambda event header maps.
+///
+/// Keys are matched case-insensitively, since event sources like API Gateway REST APIs
+/// deliver headers with their original case. Works with `HashMap<String, String>`,
+/// `BTreeMap<String, String>` and JSON objects (`serde_json::Map`), in which case only
+/// string values are extracted.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use telemetry_rust::propagation::{MapExtractor, extract_context_from};
+///
+/// let headers = HashMap::from([(
+///     "Traceparent".to_owned(),
+///     "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".to_owned(),
+/// )]);
+/// let context = extract_context_from(&MapExtractor(&headers));
+/// ```
+pub struct MapExtractor<'a, M>(pub &'a M);
+
+/// Read-only access to string maps supported by [`MapExtractor`].
+trait StringMap {
+    fn entries(&self) -> impl Iterator<Item = (&str, Option<&str>)>;
+    fn get_exact(&self, key: &str) -> Option<&str>;
+}
+
+impl<S: std::hash::BuildHasher> StringMap for HashMap<String, String, S> {
+    fn entries(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.iter().map(|(k, v)| (k.as_str(), Some(v.as_str())))
+    }
+
+    fn get_exact(&self, key: &str) -> Option<&str> {
+        self.get(key).map(String::as_str)
+    }
+}
+
+impl StringMap for BTreeMap<String, String> {
+    fn entries(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.iter().map(|(k, v)| (k.as_str(), Some(v.as_str())))
+    }
+
+    fn get_exact(&self, key: &str) -> Option<&str> {
+        self.get(key).map(String::as_str)
+    }
+}
+
+impl StringMap for serde_json::Map<String, serde_json::Value> {
+    fn entries(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    fn get_exact(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(serde_json::Value::as_str)
+    }
+}
+
+impl<M: StringMap> Extractor for MapExtractor<'_, M> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get_exact(key).or_else(|| {
+            self.0
+                .entries()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .and_then(|(_, v)| v)
+        })
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.entries().map(|(k, _)| k).collect()
+    }
+}
+
+impl<S: std::hash::BuildHasher> Injector for MapInjector<'_, HashMap<String, String, S>> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_ascii_lowercase(), value);
+    }
+}
+
+impl Injector for MapInjector<'_, BTreeMap<String, String>> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_ascii_lowercase(), value);
+    }
+}
+
+impl Injector for MapInjector<'_, serde_json::Map<String, serde_json::Value>> {
+    fn set(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_ascii_lowercase(), value.into());
+    }
+}
+
+/// Injector for gRPC request and response metadata.
+///
+/// Fields with invalid metadata keys or values are skipped.
+#[cfg(feature = "tonic")]
+pub struct MetadataInjector<'a>(pub &'a mut tonic::metadata::MetadataMap);
+
+#[cfg(feature = "tonic")]
+impl Injector for MetadataInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let Ok(key) = tonic::metadata::MetadataKey::from_bytes(key.as_bytes())
+            && let Ok(value) = tonic::metadata::MetadataValue::try_from(value)
+        {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+/// Extractor for gRPC request and response metadata.
+///
+/// Binary metadata values are ignored.
+#[cfg(feature = "tonic")]
+pub struct MetadataExtractor<'a>(pub &'a tonic::metadata::MetadataMap);
+
+#[cfg(feature = "tonic")]
+impl Extractor for MetadataExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .map(|key| match key {
+                tonic::metadata::KeyRef::Ascii(key) => key.as_str(),
+                tonic::metadata::KeyRef::Binary(key) => key.as_str(),
+            })
+            .collect()
+    }
+}
+
+/// Injector for SQS and SNS message attributes.
+///
+/// Fields are injected as `String` attributes. Since SQS and SNS accept at most 10
+/// message attributes, fields that would exceed the limit are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// # #[cfg(feature = "aws-sqs")]
+/// # {
+/// use std::collections::HashMap;
+/// use telemetry_rust::propagation::{MessageAttributesInjector, inject_context_into};
+///
+/// let mut attributes = HashMap::<String, aws_sdk_sqs::types::MessageAttributeValue>::new();
+/// inject_context_into(&mut MessageAttributesInjector(&mut attributes));
+/// # }
+/// ```
+#[cfg(any(feature = "aws-sqs", feature = "aws-sns"))]
+pub struct MessageAttributesInjector<'a, V>(pub &'a mut HashMap<String, V>);
+
+/// Extractor for SQS and SNS message attributes.
+///
+/// Only attributes with a string value are extracted.
+#[cfg(any(feature = "aws-sqs", feature = "aws-sns"))]
+pub struct MessageAttributesExtractor<'a, V>(pub &'a HashMap<String, V>);
+
+macro_rules! message_attributes_carrier {
+    ($feature: literal, $value: ty) => {
+        #[cfg(feature = $feature)]
+        impl Injector for MessageAttributesInjector<'_, $value> {
+            fn set(&mut self, key: &str, value: String) {
+                if self.0.len() >= MAX_MESSAGE_ATTRIBUTES && !self.0.contains_key(key) {
+                    return;
+                }
+                if let Ok(value) = <$value>::builder()
+                    .data_type("String")
+                    .string_value(value)
+                    .build()
+                {
+                    self.0.insert(key.to_owned(), value);
+                }
+            }
+        }
+
+        #[cfg(feature = $feature)]
+        impl Extractor for MessageAttributesExtractor<'_, $value> {
+            fn get(&self, key: &str) -> Option<&str> {
+                self.0.get(key).and_then(|value| value.string_value())
+            }
+
+            fn keys(&self) -> Vec<&str> {
+                self.0.keys().map(String::as_str).collect()
+            }
+        }
+    };
+}
+
+message_attributes_carrier!("aws-sqs", aws_sdk_sqs::types::MessageAttributeValue);
+message_attributes_carrier!("aws-sns", aws_sdk_sns::types::MessageAttributeValue);
+
+/// Injects the current OpenTelemetry context into any carrier, using the global
+/// text map propagator.
+///
+/// # Example
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use telemetry_rust::propagation::inject_context_into;
+///
+/// let mut carrier = HashMap::<String, String>::new();
+/// inject_context_into(&mut carrier);
+/// ```
+pub fn inject_context_into(injector: &mut dyn Injector) {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&crate::baggage::current_context(), injector);
+    });
+}
+
+/// Extracts OpenTelemetry context from any carrier, using the global text map
+/// propagator.
+///
+/// If the carrier has no trace context, the returned context has no active span.
+#[must_use]
+pub fn extract_context_from(extractor: &dyn Extractor) -> Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(extractor)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_map_carriers() {
+        let mut map = BTreeMap::new();
+        MapInjector(&mut map).set("TraceParent", TRACEPARENT.to_owned());
+        assert!(map.keys().collect::<Vec<_>>() == ["traceparent"]);
+
+        let headers = HashMap::from([("Traceparent".to_owned(), TRACEPARENT.to_owned())]);
+        assert!(MapExtractor(&headers).get("traceparent") == Some(TRACEPARENT));
+
+        let json = serde_json::json!({ "X-Amzn-Trace-Id": "Root=1-5759e988-bd862e3fe1be46a994272793", "count": 1 });
+        let json = json.as_object().unwrap();
+        assert!(MapExtractor(json).get("x-amzn-trace-id").is_some());
+        assert!(MapExtractor(json).get("count").is_none());
+    }
+
+    #[cfg(feature = "tonic")]
+    #[test]
+    fn test_metadata_carriers() {
+        let mut metadata = tonic::metadata::MetadataMap::new();
+        MetadataInjector(&mut metadata).set("traceparent", TRACEPARENT.to_owned());
+        MetadataInjector(&mut metadata).set("invalid key", "value".to_owned());
+
+        let extractor = MetadataExtractor(&metadata);
+        assert!(extractor.get("traceparent") == Some(TRACEPARENT));
+        assert!(extractor.keys() == ["traceparent"]);
+    }
+
+    #[cfg(feature = "aws-sqs")]
+    #[test]
+    fn test_message_attributes_carriers() {
+        use aws_sdk_sqs::types::MessageAttributeValue;
+
+        let mut attributes = (0..9)
+            .map(|i| {
+                let value = MessageAttributeValue::builder()
+                    .data_type("Number")
+                    .string_value(i.to_string())
+                    .build()
+                    .unwrap();
+                (format!("attribute-{i}"), value)
+            })
+            .collect::<HashMap<_, _>>();
+        let mut injector = MessageAttributesInjector(&mut attributes);
+        injector.set("traceparent", TRACEPARENT.to_owned());
+        injector.set("tracestate", "key=value".to_owned());
+
+        assert!(attributes.len() == 10);
+        assert!(attributes["traceparent"].data_type() == "String");
+        let extractor = MessageAttributesExtractor(&attributes);
+        assert!(extractor.get("traceparent") == Some(TRACEPARENT));
+        assert!(extractor.get("tracestate").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+use opentelemetry::{
+    Context,
+    propagation::{
+        Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter,
+    },
+    trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState},
+};
+use std::sync::LazyLock;
+
+const TRACE_ID_HEADER: &str = "x-datadog-trace-id";
+const PARENT_ID_HEADER: &str = "x-datadog-parent-id";
+const SAMPLING_PRIORITY_HEADER: &str = "x-datadog-sampling-priority";
+const TAGS_HEADER: &str = "x-datadog-tags";
+
+/// Propagation tag carrying the upper 64 bits of 128-bit trace ids.
+const TRACE_ID_HIGH_TAG: &str = "_dd.p.tid";
+
+static DATADOG_HEADER_FIELDS: LazyLock<[String; 4]> = LazyLock::new(|| {
+    [
+        TRACE_ID_HEADER.to_owned(),
+        PARENT_ID_HEADER.to_owned(),
+        SAMPLING_PRIORITY_HEADER.to_owned(),
+        TAGS_HEADER.to_owned(),
+    ]
+});
+
+/// Propagator for the Datadog `x-datadog-*` headers.
+///
+/// Datadog tracers propagate the lower 64 bits of the trace id and the parent span id
+/// as decimal numbers in `x-datadog-trace-id` and `x-datadog-parent-id`, and the upper
+/// 64 bits of 128-bit trace ids as hex in the `_dd.p.tid` tag of `x-datadog-tags`.
+/// This propagator converts them to and from W3C trace ids, so that traces can be
+/// joined across Datadog and OpenTelemetry services.
+///
+/// The trace is sampled if `x-datadog-sampling-priority` is positive. A missing or
+/// invalid priority is treated as sampled, like the auto-keep priority of Datadog
+/// tracers. Other propagation tags are not preserved.
+///
+/// Enabled with `datadog` in `OTEL_PROPAGATORS` (requires "datadog" feature).
+#[derive(Debug, Clone, Default)]
+pub struct DatadogPropagator {
+    _private: (),
+}
+
+impl DatadogPropagator {
+    /// Creates a new Datadog propagator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        let trace_id_low = parse_decimal_id(extractor.get(TRACE_ID_HEADER)?)?;
+        let span_id = parse_decimal_id(extractor.get(PARENT_ID_HEADER)?)?;
+        let trace_id_high = extractor
+            .get(TAGS_HEADER)
+            .and_then(|tags| find_tag(tags, TRACE_ID_HIGH_TAG))
+            .and_then(|tid| {
+                let valid = tid.len() == 16 && tid.bytes().all(|b| b.is_ascii_hexdigit());
+                valid.then(|| u64::from_str_radix(tid, 16).ok()).flatten()
+            })
+            .unwrap_or_default();
+        let sampled = extractor
+            .get(SAMPLING_PRIORITY_HEADER)
+            .and_then(|priority| priority.trim().parse::<i32>().ok())
+            .is_none_or(|priority| priority > 0);
+
+        let trace_id = (u128::from(trace_id_high) << 64) | u128::from(trace_id_low);
+        let trace_flags = if sampled {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+
+        let span_context = SpanContext::new(
+            TraceId::from(trace_id),
+            SpanId::from(span_id),
+            trace_flags,
+            true,
+            TraceState::default(),
+        );
+        span_context.is_valid().then_some(span_context)
+    }
+}
+
+impl TextMapPropagator for DatadogPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if !span_context.is_valid() {
+            return;
+        }
+
+        let trace_id = u128::from_be_bytes(span_context.trace_id().to_bytes());
+        let span_id = u64::from_be_bytes(span_context.span_id().to_bytes());
+        let (trace_id_high, trace_id_low) = ((trace_id >> 64) as u64, trace_id as u64);
+
+        injector.set(TRACE_ID_HEADER, trace_id_low.to_string());
+        injector.set(PARENT_ID_HEADER, span_id.to_string());
+        let priority = if span_context.is_sampled() { "1" } else { "0" };
+        injector.set(SAMPLING_PRIORITY_HEADER, priority.to_owned());
+        if trace_id_high != 0 {
+            injector.set(
+                TAGS_HEADER,
+                format!("{TRACE_ID_HIGH_TAG}={trace_id_high:016x}"),
+            );
+        }
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        match self.extract_span_context(extractor) {
+            Some(span_context) => cx.with_remote_span_context(span_context),
+            None => cx.clone(),
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(DATADOG_HEADER_FIELDS.as_ref())
+    }
+}
+
+fn parse_decimal_id(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn find_tag<'a>(tags: &'a str, key: &str) -> Option<&'a str> {
+    tags.split(',').find_map(|tag| {
+        let (tag_key, value) = tag.split_once('=')?;
+        (tag_key.trim() == key).then(|| value.trim())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use rstest::rstest;
+    use std::collections::HashMap;
+
+    fn headers(fields: &[(&str, &str)]) -> HashMap<String, String> {
+        fields
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678"), ("x-datadog-sampling-priority", "1")], Some(("000000000000000000000000000004d2", "000000000000162e", true)))]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678"), ("x-datadog-sampling-priority", "-1")], Some(("000000000000000000000000000004d2", "000000000000162e", false)))]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678")], Some(("000000000000000000000000000004d2", "000000000000162e", true)))]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678"), ("x-datadog-sampling-priority", "keep")], Some(("000000000000000000000000000004d2", "000000000000162e", true)))]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678"), ("x-datadog-tags", "_dd.p.dm=-0,_dd.p.tid=640cfd8d00000000")], Some(("640cfd8d0000000000000000000004d2", "000000000000162e", true)))]
+    #[case(&[("x-datadog-trace-id", "1234"), ("x-datadog-parent-id", "5678"), ("x-datadog-tags", "_dd.p.tid=xyz")], Some(("000000000000000000000000000004d2", "000000000000162e", true)))]
+    #[case(&[("x-datadog-trace-id", "0"), ("x-datadog-parent-id", "5678")], None)]
+    #[case(&[("x-datadog-trace-id", "-1234"), ("x-datadog-parent-id", "5678")], None)]
+    #[case(&[("x-datadog-trace-id", "1234")], None)]
+    fn test_extract_datadog_headers(
+        #[case] fields: &[(&str, &str)],
+        #[case] expected: Option<(&str, &str, bool)>,
+    ) {
+        let cx = DatadogPropagator::new().extract(&headers(fields));
+        let span = cx.span();
+        let span_context = span.span_context();
+
+        match expected {
+            Some((trace_id, span_id, sampled)) => {
+                assert!(span_context.trace_id() == TraceId::from_hex(trace_id).unwrap());
+                assert!(span_context.span_id() == SpanId::from_hex(span_id).unwrap());
+                assert!(span_context.is_sampled() == sampled);
+                assert!(span_context.is_remote());
+            }
+            None => assert!(!span_context.is_valid()),
+        }
+    }
+
+    #[rstest]
+    #[case("4bf92f3577b34da6a3ce929d0e0e4736", Some("_dd.p.tid=4bf92f3577b34da6"))]
+    #[case("0000000000000000a3ce929d0e0e4736", None)]
+    fn test_inject_datadog_headers(#[case] trace_id: &str, #[case] tags: Option<&str>) {
+        let span_context = SpanContext::new(
+            TraceId::from_hex(trace_id).unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+
+        let mut injected = HashMap::new();
+        DatadogPropagator::new().inject_context(&cx, &mut injected);
+
+        assert!(injected[TRACE_ID_HEADER] == "11803532876627986230");
+        assert!(injected[PARENT_ID_HEADER] == "67667974448284343");
+        assert!(injected[SAMPLING_PRIORITY_HEADER] == "1");
+        assert!(injected.get(TAGS_HEADER).map(String::as_str) == tags);
+
+        let extracted = DatadogPropagator::new().extract(&injected);
+        assert!(extracted.span().span_context() == &span_context);
+    }
+}
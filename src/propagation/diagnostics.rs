@@ -0,0 +1,299 @@
+use opentelemetry::{
+    Context,
+    propagation::{
+        Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter,
+    },
+    trace::{SpanContext, TraceContextExt},
+};
+use std::{
+    collections::BTreeSet,
+    sync::{
+        LazyLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use super::Propagator;
+
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+static STARTED_AT: LazyLock<Instant> = LazyLock::new(Instant::now);
+static MALFORMED: Counter = Counter::new();
+static CONFLICTING: Counter = Counter::new();
+static MISSING: Counter = Counter::new();
+
+/// Counts of extraction problems found by propagators with diagnostics enabled,
+/// see [`TextMapSplitPropagatorBuilder::diagnostics`](super::TextMapSplitPropagatorBuilder::diagnostics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiagnosticsCounts {
+    /// Requests with a propagation header that could not be parsed.
+    pub malformed: u64,
+    /// Requests with contexts of several propagators disagreeing on the trace or parent.
+    pub conflicting: u64,
+    /// Requests without any trace context.
+    pub missing: u64,
+}
+
+/// Returns the counts of extraction problems since the process started.
+pub fn diagnostics_counts() -> DiagnosticsCounts {
+    DiagnosticsCounts {
+        malformed: MALFORMED.total.load(Ordering::Relaxed),
+        conflicting: CONFLICTING.total.load(Ordering::Relaxed),
+        missing: MISSING.total.load(Ordering::Relaxed),
+    }
+}
+
+/// Returns the name of the propagator which extracted the span context of `cx`.
+///
+/// Only available for contexts extracted by propagators with diagnostics enabled.
+pub fn extracted_by(cx: &Context) -> Option<&str> {
+    cx.get::<ExtractedBy>()
+        .map(|extracted_by| extracted_by.0.as_str())
+}
+
+#[derive(Debug)]
+struct ExtractedBy(String);
+
+/// Problem counter with a rate-limited log.
+struct Counter {
+    total: AtomicU64,
+    suppressed: AtomicU64,
+    next_log_ms: AtomicU64,
+}
+
+impl Counter {
+    const fn new() -> Self {
+        Self {
+            total: AtomicU64::new(0),
+            suppressed: AtomicU64::new(0),
+            next_log_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts a problem, returning the number of problems suppressed since the last
+    /// log if this one should be logged.
+    fn record(&self) -> Option<u64> {
+        self.total.fetch_add(1, Ordering::Relaxed);
+        let now_ms = STARTED_AT.elapsed().as_millis() as u64;
+        let next_log_ms = self.next_log_ms.load(Ordering::Relaxed);
+        let log_now = now_ms >= next_log_ms
+            && self
+                .next_log_ms
+                .compare_exchange(
+                    next_log_ms,
+                    now_ms + LOG_INTERVAL.as_millis() as u64,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok();
+        if log_now {
+            Some(self.suppressed.swap(0, Ordering::Relaxed))
+        } else {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+    }
+}
+
+/// Extract propagator applying propagators one by one to report extraction problems.
+///
+/// Propagators are listed by precedence, like in
+/// [`TextMapSplitPropagatorBuilder`](super::TextMapSplitPropagatorBuilder).
+pub(super) struct DiagnosticsPropagator {
+    propagators: Vec<(String, Propagator)>,
+    fields: Vec<String>,
+}
+
+impl DiagnosticsPropagator {
+    pub(super) fn new(propagators: Vec<(String, Propagator)>) -> Self {
+        let fields = propagators
+            .iter()
+            .flat_map(|(_, propagator)| propagator.fields())
+            .map(String::from)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        Self {
+            propagators,
+            fields,
+        }
+    }
+}
+
+impl std::fmt::Debug for DiagnosticsPropagator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DiagnosticsPropagator")
+            .field(
+                "propagators",
+                &self
+                    .propagators
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl TextMapPropagator for DiagnosticsPropagator {
+    fn inject_context(&self, _: &Context, _: &mut dyn Injector) {}
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let mut result = cx.clone();
+        let mut extracted: Vec<(&str, SpanContext)> = Vec::new();
+        let mut malformed = Vec::new();
+
+        // lowest precedence first, so that higher precedence propagators override it
+        for (name, propagator) in self.propagators.iter().rev() {
+            let next = propagator.extract_with_context(&result, extractor);
+            let span_context = next.span().span_context().clone();
+            if span_context.is_valid() && span_context != *result.span().span_context() {
+                extracted.push((name, span_context));
+            } else if name != "baggage"
+                && propagator
+                    .fields()
+                    .any(|field| extractor.get(field).is_some())
+            {
+                // the context is unchanged either because the header is invalid, or
+                // because it has the same context as a lower precedence propagator
+                let own = propagator.extract(extractor);
+                match own.span().span_context() {
+                    own if own.is_valid() => extracted.push((name, own.clone())),
+                    _ => malformed.push(name.as_str()),
+                }
+            }
+            result = next;
+        }
+
+        if !malformed.is_empty()
+            && let Some(suppressed) = MALFORMED.record()
+        {
+            let headers = malformed
+                .iter()
+                .flat_map(|name| self.propagator_fields(name, extractor))
+                .collect::<Vec<_>>();
+            tracing::warn!(
+                target: "otel::propagation",
+                propagators = ?malformed,
+                ?headers,
+                suppressed,
+                "malformed trace context",
+            );
+        }
+        let conflicting = extracted.windows(2).any(|pair| {
+            pair[0].1.trace_id() != pair[1].1.trace_id()
+                || pair[0].1.span_id() != pair[1].1.span_id()
+        });
+        if conflicting && let Some(suppressed) = CONFLICTING.record() {
+            let contexts = extracted
+                .iter()
+                .map(|(name, sc)| format!("{name}: {}-{}", sc.trace_id(), sc.span_id()))
+                .collect::<Vec<_>>();
+            tracing::warn!(
+                target: "otel::propagation",
+                ?contexts,
+                suppressed,
+                "conflicting trace contexts",
+            );
+        }
+        if extracted.is_empty()
+            && malformed.is_empty()
+            && let Some(suppressed) = MISSING.record()
+        {
+            tracing::debug!(target: "otel::propagation", suppressed, "missing trace context");
+        }
+
+        match extracted.last() {
+            Some((name, _)) => result.with_value(ExtractedBy((*name).to_owned())),
+            None => result,
+        }
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(self.fields.as_slice())
+    }
+}
+
+impl DiagnosticsPropagator {
+    fn propagator_fields<'a>(
+        &'a self,
+        name: &'a str,
+        extractor: &'a dyn Extractor,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.propagators
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .flat_map(|(_, propagator)| propagator.fields())
+            .filter_map(move |field| {
+                extractor
+                    .get(field)
+                    .map(|value| format!("{field}: {value:?}"))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::propagation::TextMapSplitPropagator;
+    use assert2::assert;
+    use rstest::rstest;
+    use serial_test::serial;
+    use std::collections::HashMap;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    const UBER_TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736:00f067aa0ba902b7:0:1";
+    const OTHER_UBER_TRACE_ID: &str = "a3ce929d0e0e4736:00f067aa0ba902b7:0:1";
+
+    #[rstest]
+    #[case(&[("traceparent", TRACEPARENT)], Some("tracecontext"), (0, 0, 0))]
+    #[case(&[("uber-trace-id", UBER_TRACE_ID)], Some("jaeger"), (0, 0, 0))]
+    #[case(&[("traceparent", TRACEPARENT), ("uber-trace-id", UBER_TRACE_ID)], Some("tracecontext"), (0, 0, 0))]
+    #[case(&[("traceparent", TRACEPARENT), ("uber-trace-id", OTHER_UBER_TRACE_ID)], Some("tracecontext"), (0, 1, 0))]
+    #[case(&[("traceparent", "00-invalid-01"), ("uber-trace-id", UBER_TRACE_ID)], Some("jaeger"), (1, 0, 0))]
+    #[case(&[("traceparent", "00-invalid-01")], None, (1, 0, 0))]
+    #[case(&[("baggage", "key=value")], None, (0, 0, 1))]
+    #[serial]
+    fn test_diagnostics(
+        #[case] headers: &[(&str, &str)],
+        #[case] expected_source: Option<&str>,
+        #[case] expected_counts: (u64, u64, u64),
+    ) {
+        let propagator = TextMapSplitPropagator::builder()
+            .extract("tracecontext")
+            .extract("jaeger")
+            .extract("baggage")
+            .diagnostics(true)
+            .build()
+            .unwrap();
+        let carrier = headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+
+        let before = diagnostics_counts();
+        let cx = propagator.extract(&carrier);
+        let after = diagnostics_counts();
+
+        assert!(extracted_by(&cx) == expected_source);
+        assert!(
+            (
+                after.malformed - before.malformed,
+                after.conflicting - before.conflicting,
+                after.missing - before.missing,
+            ) == expected_counts
+        );
+    }
+
+    #[test]
+    fn test_counter_rate_limit() {
+        let counter = Counter::new();
+
+        assert!(counter.record() == Some(0));
+        assert!(counter.record().is_none());
+        assert!(counter.record().is_none());
+        assert!(counter.total.load(Ordering::Relaxed) == 3);
+        assert!(counter.suppressed.load(Ordering::Relaxed) == 2);
+    }
+}
@@ -0,0 +1,169 @@
+use opentelemetry::{
+    Context,
+    propagation::{
+        Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter,
+    },
+    trace::{SpanContext, TraceContextExt, TraceFlags, TraceState},
+};
+use percent_encoding::percent_decode_str;
+use std::sync::LazyLock;
+
+use super::{
+    extract_prefixed_baggage, inject_prefixed_baggage, parse_span_id, parse_trace_id,
+};
+
+const JAEGER_HEADER: &str = "uber-trace-id";
+const JAEGER_BAGGAGE_PREFIX: &str = "uberctx-";
+const DEPRECATED_PARENT_SPAN: &str = "0";
+
+const FLAG_SAMPLED: u8 = 0x01;
+const FLAG_DEBUG: u8 = 0x02;
+
+static JAEGER_HEADER_FIELD: LazyLock<[String; 1]> =
+    LazyLock::new(|| [JAEGER_HEADER.to_owned()]);
+
+/// Propagator for the Jaeger native `uber-trace-id` header.
+///
+/// The header has the format `{trace-id}:{span-id}:{parent-span-id}:{flags}`, where the
+/// parent span id is deprecated and always injected as `0`. Baggage is propagated in
+/// `uberctx-{key}` headers.
+///
+/// Enabled with `jaeger` in `OTEL_PROPAGATORS`.
+#[derive(Debug, Clone, Default)]
+pub struct JaegerPropagator {
+    _private: (),
+}
+
+impl JaegerPropagator {
+    /// Creates a new Jaeger propagator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        let header = extractor.get(JAEGER_HEADER)?;
+        // some Jaeger clients url-encode the header value
+        let header = percent_decode_str(header).decode_utf8().ok()?;
+
+        let parts = header.split(':').collect::<Vec<_>>();
+        let [trace_id, span_id, _parent_span_id, flags] = parts.as_slice() else {
+            return None;
+        };
+
+        let trace_id = parse_trace_id(trace_id)?;
+        let span_id = parse_span_id(span_id)?;
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        let trace_flags = if flags & (FLAG_SAMPLED | FLAG_DEBUG) != 0 {
+            TraceFlags::SAMPLED
+        } else {
+            TraceFlags::default()
+        };
+
+        let span_context =
+            SpanContext::new(trace_id, span_id, trace_flags, true, TraceState::default());
+        span_context.is_valid().then_some(span_context)
+    }
+}
+
+impl TextMapPropagator for JaegerPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if span_context.is_valid() {
+            let flags = if span_context.is_sampled() {
+                FLAG_SAMPLED
+            } else {
+                0
+            };
+            let header = format!(
+                "{}:{}:{DEPRECATED_PARENT_SPAN}:{flags:x}",
+                span_context.trace_id(),
+                span_context.span_id(),
+            );
+            injector.set(JAEGER_HEADER, header);
+        }
+
+        inject_prefixed_baggage(cx, injector, JAEGER_BAGGAGE_PREFIX);
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let cx = match self.extract_span_context(extractor) {
+            Some(span_context) => cx.with_remote_span_context(span_context),
+            None => cx.clone(),
+        };
+
+        extract_prefixed_baggage(&cx, extractor, JAEGER_BAGGAGE_PREFIX)
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(JAEGER_HEADER_FIELD.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::{
+        KeyValue,
+        baggage::BaggageExt,
+        trace::{SpanId, TraceId},
+    };
+    use rstest::rstest;
+    use std::collections::HashMap;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const SPAN_ID: &str = "00f067aa0ba902b7";
+
+    #[rstest]
+    #[case(format!("{TRACE_ID}:{SPAN_ID}:0:1"), Some((TRACE_ID, true)))]
+    #[case(format!("{TRACE_ID}%3A{SPAN_ID}%3A0%3A1"), Some((TRACE_ID, true)))]
+    #[case(format!("{TRACE_ID}:{SPAN_ID}:0:2"), Some((TRACE_ID, true)))]
+    #[case(format!("{TRACE_ID}:{SPAN_ID}:0:0"), Some((TRACE_ID, false)))]
+    #[case(format!("a3ce929d0e0e4736:{SPAN_ID}:0:1"), Some(("0000000000000000a3ce929d0e0e4736", true)))]
+    #[case(format!("{TRACE_ID}:{SPAN_ID}:0"), None)]
+    #[case(format!("{TRACE_ID}:0:0:1"), None)]
+    #[case(format!("xyz:{SPAN_ID}:0:1"), None)]
+    fn test_extract_uber_trace_id(
+        #[case] header: String,
+        #[case] expected: Option<(&str, bool)>,
+    ) {
+        let headers = HashMap::from([(JAEGER_HEADER.to_owned(), header)]);
+        let cx = JaegerPropagator::new().extract(&headers);
+        let span = cx.span();
+        let span_context = span.span_context();
+
+        match expected {
+            Some((trace_id, sampled)) => {
+                assert!(span_context.trace_id() == TraceId::from_hex(trace_id).unwrap());
+                assert!(span_context.span_id() == SpanId::from_hex(SPAN_ID).unwrap());
+                assert!(span_context.is_sampled() == sampled);
+                assert!(span_context.is_remote());
+            }
+            None => assert!(!span_context.is_valid()),
+        }
+    }
+
+    #[test]
+    fn test_inject_and_extract_baggage() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex(TRACE_ID).unwrap(),
+            SpanId::from_hex(SPAN_ID).unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new()
+            .with_remote_span_context(span_context.clone())
+            .with_baggage([KeyValue::new("user", "alice smith")]);
+
+        let mut headers = HashMap::new();
+        JaegerPropagator::new().inject_context(&cx, &mut headers);
+        assert!(headers[JAEGER_HEADER] == format!("{TRACE_ID}:{SPAN_ID}:0:1"));
+        assert!(headers["uberctx-user"] == "alice%20smith");
+
+        let extracted = JaegerPropagator::new().extract(&headers);
+        assert!(extracted.span().span_context().span_id() == span_context.span_id());
+        assert!(extracted.baggage().get("user").unwrap().as_str() == "alice smith");
+    }
+}
@@ -0,0 +1,609 @@
+//! Context propagation utilities for distributed tracing across service boundaries.
+
+use opentelemetry::{
+    Context,
+    baggage::{Baggage, BaggageExt},
+    propagation::{
+        Extractor, Injector, TextMapCompositePropagator, TextMapPropagator,
+        text_map_propagator::FieldIter,
+    },
+    trace::{SpanId, TraceId},
+};
+#[cfg(feature = "xray")]
+use opentelemetry_aws::trace::XrayPropagator;
+use opentelemetry_sdk::{error::OTelSdkError, propagation::TraceContextPropagator};
+#[cfg(feature = "zipkin")]
+#[allow(deprecated)]
+use opentelemetry_zipkin::{B3Encoding, Propagator as B3Propagator};
+use percent_encoding::{NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use std::collections::BTreeSet;
+
+use crate::{baggage::BaggagePropagator, util};
+use diagnostics::DiagnosticsPropagator;
+
+mod carrier;
+#[cfg(feature = "datadog")]
+mod datadog;
+mod diagnostics;
+mod jaeger;
+mod ottrace;
+
+pub use carrier::{MapExtractor, MapInjector, extract_context_from, inject_context_into};
+#[cfg(any(feature = "aws-sqs", feature = "aws-sns"))]
+pub use carrier::{MessageAttributesExtractor, MessageAttributesInjector};
+#[cfg(feature = "tonic")]
+pub use carrier::{MetadataExtractor, MetadataInjector};
+#[cfg(feature = "datadog")]
+pub use datadog::DatadogPropagator;
+pub use diagnostics::{DiagnosticsCounts, diagnostics_counts, extracted_by};
+pub use jaeger::JaegerPropagator;
+pub use ottrace::OtTracePropagator;
+
+/// Type alias for a boxed text map propagator.
+///
+/// This type represents a thread-safe, heap-allocated text map propagator that can be
+/// used for OpenTelemetry context propagation across service boundaries.
+pub type Propagator = Box<dyn TextMapPropagator + Send + Sync>;
+
+/// A no-op propagator that performs no context injection or extraction.
+///
+/// This propagator can be used when context propagation is explicitly disabled
+/// or not needed. It implements the [`TextMapPropagator`] trait but performs
+/// no actual propagation operations.
+#[derive(Debug)]
+pub struct NonePropagator;
+
+impl TextMapPropagator for NonePropagator {
+    fn inject_context(&self, _: &Context, _: &mut dyn Injector) {}
+
+    fn extract_with_context(&self, cx: &Context, _: &dyn Extractor) -> Context {
+        cx.clone()
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(&[])
+    }
+}
+
+/// A text map propagator that uses different propagators for injection and extraction.
+///
+/// This propagator allows for asymmetric context propagation where different
+/// propagation strategies can be used for outgoing requests (injection) versus
+/// incoming requests (extraction). This is useful when you need to maintain
+/// compatibility with multiple tracing systems or protocols.
+///
+/// # Use Cases
+///
+/// - Migrating between tracing systems while maintaining compatibility
+/// - Supporting multiple trace context formats in a single service
+/// - Using environment-specific propagation strategies
+#[derive(Debug)]
+pub struct TextMapSplitPropagator {
+    extract_propagator: Propagator,
+    inject_propagator: Propagator,
+    fields: Vec<String>,
+}
+
+impl TextMapSplitPropagator {
+    /// Creates a new split propagator with separate propagators for extraction and injection.
+    ///
+    /// # Arguments
+    ///
+    /// - `extract_propagator`: Propagator used for extracting context from incoming requests
+    /// - `inject_propagator`: Propagator used for injecting context into outgoing requests
+    ///
+    /// # Returns
+    ///
+    /// A new [`TextMapSplitPropagator`] instance
+    pub fn new(extract_propagator: Propagator, inject_propagator: Propagator) -> Self {
+        let mut fields = BTreeSet::from_iter(extract_propagator.fields());
+        fields.extend(inject_propagator.fields());
+        let fields = fields.into_iter().map(String::from).collect();
+
+        Self {
+            extract_propagator,
+            inject_propagator,
+            fields,
+        }
+    }
+
+    /// Creates a builder to compose propagators for extraction and injection.
+    ///
+    /// See [`TextMapSplitPropagatorBuilder`] for details.
+    pub fn builder() -> TextMapSplitPropagatorBuilder {
+        TextMapSplitPropagatorBuilder::default()
+    }
+
+    /// Creates a split propagator based on the `OTEL_PROPAGATORS` and
+    /// `OTEL_PROPAGATORS_INJECT` environment variables.
+    ///
+    /// All propagators listed in `OTEL_PROPAGATORS` are composed together for extraction.
+    /// All propagators listed in `OTEL_PROPAGATORS_INJECT` are composed together for
+    /// injection. If `OTEL_PROPAGATORS_INJECT` is not set, the first propagator of
+    /// `OTEL_PROPAGATORS` is used for injection.
+    /// If neither variable is set, the [`Default`] propagator is used, and if only
+    /// `OTEL_PROPAGATORS_INJECT` is set, the default propagators are used for extraction.
+    ///
+    /// Setting `OTEL_PROPAGATORS_DIAGNOSTICS=true` enables extraction diagnostics, see
+    /// [`TextMapSplitPropagatorBuilder::diagnostics`].
+    ///
+    /// # Environment Variable Format
+    ///
+    /// Both variables should contain a comma-separated list of propagator names:
+    /// - `tracecontext`: W3C Trace Context propagator
+    /// - `baggage`: W3C Baggage propagator
+    /// - `b3`: B3 single header propagator (requires "zipkin" feature)
+    /// - `b3multi`: B3 multiple header propagator (requires "zipkin" feature)
+    /// - `xray`: AWS X-Ray propagator (requires "xray" feature)
+    /// - `jaeger`: Jaeger `uber-trace-id` propagator, see [`JaegerPropagator`]
+    /// - `ottrace`: OpenTracing `ot-tracer-*` propagator, see [`OtTracePropagator`]
+    /// - `datadog`: Datadog `x-datadog-*` propagator (requires "datadog" feature)
+    /// - `none`: No-op propagator
+    ///
+    /// # Returns
+    ///
+    /// A configured [`TextMapSplitPropagator`] on success, or an [`OTelSdkError`] if
+    /// the environment variables contain unsupported propagator names.
+    ///
+    /// # Examples
+    ///
+    /// ```bash
+    /// export OTEL_PROPAGATORS=tracecontext,b3,baggage
+    /// export OTEL_PROPAGATORS_INJECT=tracecontext,b3multi,baggage
+    /// ```
+    ///
+    /// ```rust
+    /// use telemetry_rust::propagation::TextMapSplitPropagator;
+    ///
+    /// let propagator = TextMapSplitPropagator::from_env()?;
+    /// # Ok::<(), opentelemetry_sdk::error::OTelSdkError>(())
+    /// ```
+    pub fn from_env() -> Result<Self, OTelSdkError> {
+        let extract = read_propagators_from_env(EXTRACT_PROPAGATORS_ENV);
+        let inject = read_propagators_from_env(INJECT_PROPAGATORS_ENV);
+        let diagnostics = util::env_var(DIAGNOSTICS_ENV).is_some_and(|value| {
+            matches!(value.trim().to_lowercase().as_str(), "true" | "1")
+        });
+        if extract.is_none() && inject.is_none() && !diagnostics {
+            return Ok(Self::default());
+        }
+        tracing::info!(
+            target: "otel::setup",
+            propagators = extract.as_deref().map(|names| names.join(",")),
+            inject_propagators = inject.as_deref().map(|names| names.join(",")),
+            diagnostics,
+        );
+
+        let mut builder = Self::builder().diagnostics(diagnostics);
+        builder = match &extract {
+            Some(names) => names.iter().fold(builder, |builder, name| {
+                builder.extract_entry(name, EXTRACT_PROPAGATORS_ENV)
+            }),
+            None => {
+                builder.extract.extend(default_extract_entries());
+                builder
+            }
+        };
+        builder = match (&inject, &extract) {
+            (Some(names), _) => names.iter().fold(builder, |builder, name| {
+                builder.inject_entry(name, INJECT_PROPAGATORS_ENV)
+            }),
+            (None, Some(names)) => names.iter().take(1).fold(builder, |builder, name| {
+                builder.inject_entry(name, EXTRACT_PROPAGATORS_ENV)
+            }),
+            (None, None) => {
+                builder.inject_propagator(Box::new(TraceContextPropagator::new()))
+            }
+        };
+
+        builder.build()
+    }
+}
+
+/// Builder composing multiple propagators into a [`TextMapSplitPropagator`].
+///
+/// Propagators are added by name (see [`TextMapSplitPropagator::from_env`] for the
+/// supported names) or as custom [`Propagator`] instances. Names are validated by
+/// [`TextMapSplitPropagatorBuilder::build`].
+///
+/// All extract propagators are applied to incoming requests, propagators added first
+/// take precedence if several of them find a context. All inject propagators write
+/// their headers to outgoing requests. If no propagator is added for extraction or
+/// injection, context is not propagated in that direction.
+///
+/// # Example
+///
+/// ```rust
+/// use telemetry_rust::propagation::TextMapSplitPropagator;
+///
+/// let propagator = TextMapSplitPropagator::builder()
+///     .extract("tracecontext")
+///     .extract("baggage")
+///     .inject("tracecontext")
+///     .inject("baggage")
+///     .build()?;
+/// # Ok::<(), opentelemetry_sdk::error::OTelSdkError>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct TextMapSplitPropagatorBuilder {
+    extract: Vec<PropagatorEntry>,
+    inject: Vec<PropagatorEntry>,
+    diagnostics: bool,
+}
+
+#[derive(Debug)]
+enum PropagatorEntry {
+    Name {
+        name: String,
+        source: &'static str,
+    },
+    Propagator {
+        name: &'static str,
+        propagator: Propagator,
+    },
+}
+
+impl PropagatorEntry {
+    fn build(self) -> Result<(String, Propagator), OTelSdkError> {
+        match self {
+            Self::Name { name, source } => {
+                let propagator = propagator_from_string(&name, source)?;
+                Ok((name, propagator))
+            }
+            Self::Propagator { name, propagator } => Ok((name.to_owned(), propagator)),
+        }
+    }
+}
+
+impl TextMapSplitPropagatorBuilder {
+    /// Adds a propagator for extraction by name, e.g. `tracecontext` or `b3`.
+    pub fn extract(self, name: impl AsRef<str>) -> Self {
+        self.extract_entry(name.as_ref(), "TextMapSplitPropagatorBuilder")
+    }
+
+    /// Adds a custom propagator for extraction.
+    pub fn extract_propagator(mut self, propagator: Propagator) -> Self {
+        self.extract.push(PropagatorEntry::Propagator {
+            name: "custom",
+            propagator,
+        });
+        self
+    }
+
+    /// Adds a propagator for injection by name, e.g. `tracecontext` or `b3`.
+    pub fn inject(self, name: impl AsRef<str>) -> Self {
+        self.inject_entry(name.as_ref(), "TextMapSplitPropagatorBuilder")
+    }
+
+    /// Adds a custom propagator for injection.
+    pub fn inject_propagator(mut self, propagator: Propagator) -> Self {
+        self.inject.push(PropagatorEntry::Propagator {
+            name: "custom",
+            propagator,
+        });
+        self
+    }
+
+    /// Enables diagnostics of context extraction, disabled by default.
+    ///
+    /// With diagnostics enabled, extract propagators are applied one by one to:
+    /// - count and log propagation headers which could not be parsed, e.g. a malformed
+    ///   `traceparent`, at `warn` level
+    /// - count and log contexts of several propagators disagreeing on the trace or
+    ///   parent span, e.g. `b3` and `traceparent` headers, at `warn` level
+    /// - count and log requests without any trace context, at `debug` level
+    /// - record which propagator extracted the context, see [`extracted_by`]
+    ///
+    /// Logs use the `otel::propagation` target and are rate-limited to one per problem
+    /// kind every 10 seconds, with the number of suppressed occurrences. Counts are
+    /// available with [`diagnostics_counts`]. Propagators that only carry baggage are
+    /// not validated.
+    pub fn diagnostics(self, enabled: bool) -> Self {
+        Self {
+            diagnostics: enabled,
+            ..self
+        }
+    }
+
+    fn extract_entry(mut self, name: &str, source: &'static str) -> Self {
+        let name = name.trim().to_lowercase();
+        self.extract.push(PropagatorEntry::Name { name, source });
+        self
+    }
+
+    fn inject_entry(mut self, name: &str, source: &'static str) -> Self {
+        let name = name.trim().to_lowercase();
+        self.inject.push(PropagatorEntry::Name { name, source });
+        self
+    }
+
+    /// Builds the split propagator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`OTelSdkError`] if a propagator name is unknown, or if it requires
+    /// a compile feature which is not enabled.
+    pub fn build(self) -> Result<TextMapSplitPropagator, OTelSdkError> {
+        let extract = self
+            .extract
+            .into_iter()
+            .map(PropagatorEntry::build)
+            .collect::<Result<Vec<_>, _>>()?;
+        let inject = self
+            .inject
+            .into_iter()
+            .map(|entry| entry.build().map(|(_, propagator)| propagator))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let extract_propagator: Propagator = if self.diagnostics {
+            Box::new(DiagnosticsPropagator::new(extract))
+        } else {
+            let propagators = extract.into_iter().rev().map(|(_, p)| p).collect();
+            compose_propagators(propagators)
+        };
+        Ok(TextMapSplitPropagator::new(
+            extract_propagator,
+            compose_propagators(inject),
+        ))
+    }
+}
+
+impl TextMapPropagator for TextMapSplitPropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        self.inject_propagator.inject_context(cx, injector)
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        self.extract_propagator.extract_with_context(cx, extractor)
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(self.fields.as_slice())
+    }
+}
+
+impl Default for TextMapSplitPropagator {
+    fn default() -> Self {
+        Self::new(
+            default_extract_propagator(),
+            Box::new(TraceContextPropagator::new()),
+        )
+    }
+}
+
+const EXTRACT_PROPAGATORS_ENV: &str = "OTEL_PROPAGATORS";
+const INJECT_PROPAGATORS_ENV: &str = "OTEL_PROPAGATORS_INJECT";
+const DIAGNOSTICS_ENV: &str = "OTEL_PROPAGATORS_DIAGNOSTICS";
+
+fn read_propagators_from_env(key: &str) -> Option<Vec<String>> {
+    let value = util::env_var(key)?;
+    let names = value
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .collect();
+    Some(names)
+}
+
+/// Default extract propagators by precedence: B3 headers override `traceparent`.
+fn default_extract_entries() -> Vec<PropagatorEntry> {
+    vec![
+        #[cfg(feature = "zipkin")]
+        #[allow(deprecated)]
+        PropagatorEntry::Propagator {
+            name: "b3",
+            propagator: Box::new(B3Propagator::with_encoding(
+                B3Encoding::SingleAndMultiHeader,
+            )),
+        },
+        PropagatorEntry::Propagator {
+            name: "tracecontext",
+            propagator: Box::new(TraceContextPropagator::new()),
+        },
+    ]
+}
+
+fn default_extract_propagator() -> Propagator {
+    let propagators = default_extract_entries()
+        .into_iter()
+        .rev()
+        .filter_map(|entry| entry.build().ok().map(|(_, propagator)| propagator))
+        .collect();
+    Box::new(TextMapCompositePropagator::new(propagators))
+}
+
+fn compose_propagators(mut propagators: Vec<Propagator>) -> Propagator {
+    match propagators.len() {
+        0 => Box::new(NonePropagator),
+        1 => propagators.remove(0),
+        _ => Box::new(TextMapCompositePropagator::new(propagators)),
+    }
+}
+
+fn propagator_from_string(v: &str, source: &str) -> Result<Propagator, OTelSdkError> {
+    match v.trim() {
+        "tracecontext" => Ok(Box::new(TraceContextPropagator::new())),
+        "baggage" => Ok(Box::new(BaggagePropagator::new())),
+        "none" => Ok(Box::new(NonePropagator)),
+        "jaeger" => Ok(Box::new(JaegerPropagator::new())),
+        "ottrace" => Ok(Box::new(OtTracePropagator::new())),
+        #[cfg(feature = "datadog")]
+        "datadog" => Ok(Box::new(DatadogPropagator::new())),
+        #[cfg(not(feature = "datadog"))]
+        "datadog" => Err(OTelSdkError::InternalFailure(format!(
+            "unsupported propagator in {source}: 'datadog', try to enable compile feature 'datadog'"
+        ))),
+        #[cfg(feature = "zipkin")]
+        #[allow(deprecated)]
+        "b3" => Ok(Box::new(B3Propagator::with_encoding(
+            B3Encoding::SingleHeader,
+        ))),
+        #[cfg(not(feature = "zipkin"))]
+        "b3" => Err(OTelSdkError::InternalFailure(format!(
+            "unsupported propagator in {source}: 'b3', try to enable compile feature 'zipkin'"
+        ))),
+        #[cfg(feature = "zipkin")]
+        #[allow(deprecated)]
+        "b3multi" => Ok(Box::new(B3Propagator::with_encoding(
+            B3Encoding::MultipleHeader,
+        ))),
+        #[cfg(not(feature = "zipkin"))]
+        "b3multi" => Err(OTelSdkError::InternalFailure(format!(
+            "unsupported propagator in {source}: 'b3multi', try to enable compile feature 'zipkin'"
+        ))),
+        #[cfg(feature = "xray")]
+        "xray" => Ok(Box::new(XrayPropagator::new())),
+        #[cfg(not(feature = "xray"))]
+        "xray" => Err(OTelSdkError::InternalFailure(format!(
+            "unsupported propagator in {source}: 'xray', try to enable compile feature 'xray'"
+        ))),
+        unknown => Err(OTelSdkError::InternalFailure(format!(
+            "unsupported propagator in {source}: {unknown:?}"
+        ))),
+    }
+}
+
+/// Parses a hex encoded trace id of up to 128 bits, e.g. a 64-bit Jaeger trace id.
+fn parse_trace_id(hex: &str) -> Option<TraceId> {
+    if hex.is_empty() || hex.len() > 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    TraceId::from_hex(hex).ok()
+}
+
+/// Parses a hex encoded span id of up to 64 bits.
+fn parse_span_id(hex: &str) -> Option<SpanId> {
+    if hex.is_empty() || hex.len() > 16 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    SpanId::from_hex(hex).ok()
+}
+
+/// Injects baggage entries as separate `{prefix}{key}` fields with url-encoded values.
+fn inject_prefixed_baggage(cx: &Context, injector: &mut dyn Injector, prefix: &str) {
+    for (key, (value, _)) in cx.baggage() {
+        let value = utf8_percent_encode(value.as_str(), NON_ALPHANUMERIC).to_string();
+        injector.set(&format!("{prefix}{key}"), value);
+    }
+}
+
+/// Extracts baggage entries from `{prefix}{key}` fields, keeping existing entries.
+fn extract_prefixed_baggage(
+    cx: &Context,
+    extractor: &dyn Extractor,
+    prefix: &str,
+) -> Context {
+    let entries = extractor
+        .keys()
+        .into_iter()
+        .filter_map(|field| {
+            let key = field
+                .get(..prefix.len())?
+                .eq_ignore_ascii_case(prefix)
+                .then(|| &field[prefix.len()..])?;
+            let value = percent_decode_str(extractor.get(field)?)
+                .decode_utf8()
+                .ok()?;
+            (!key.is_empty()).then(|| (key.to_owned(), value.into_owned()))
+        })
+        .collect::<Vec<_>>();
+    if entries.is_empty() {
+        return cx.clone();
+    }
+
+    let mut baggage = cx
+        .baggage()
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect::<Baggage>();
+    for (key, value) in entries {
+        baggage.insert(key, value);
+    }
+    cx.with_baggage(baggage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::trace::{
+        SpanContext, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn init_tracing_failed_on_invalid_propagator() {
+        assert!(let Err(_) = propagator_from_string("xxxxxx", "test"));
+    }
+
+    #[test]
+    fn legacy_propagators_from_string() {
+        assert!(let Ok(_) = propagator_from_string("jaeger", "test"));
+        assert!(let Ok(_) = propagator_from_string("ottrace", "test"));
+    }
+
+    #[test]
+    fn builder_composes_inject_propagators() {
+        let propagator = TextMapSplitPropagator::builder()
+            .extract("jaeger")
+            .extract("tracecontext")
+            .inject("tracecontext")
+            .inject(" Jaeger ")
+            .build()
+            .unwrap();
+
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+        let mut headers = HashMap::new();
+        propagator.inject_context(&cx, &mut headers);
+
+        assert!(headers.contains_key("traceparent"));
+        assert!(headers.contains_key("uber-trace-id"));
+
+        let extracted = propagator.extract(&headers);
+        assert!(extracted.span().span_context() == &span_context);
+
+        let fields = propagator.fields().collect::<Vec<_>>();
+        assert!(fields.contains(&"uber-trace-id"));
+        assert!(fields.contains(&"traceparent"));
+    }
+
+    #[test]
+    fn builder_without_inject_propagators_injects_nothing() {
+        let propagator = TextMapSplitPropagator::builder()
+            .extract("tracecontext")
+            .build()
+            .unwrap();
+        let cx = Context::new().with_remote_span_context(SpanContext::new(
+            TraceId::from(1),
+            SpanId::from(1),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        ));
+        let mut headers = HashMap::new();
+        propagator.inject_context(&cx, &mut headers);
+
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn builder_fails_on_unknown_propagator() {
+        let result = TextMapSplitPropagator::builder()
+            .extract("tracecontext")
+            .inject("tracecontext")
+            .inject("w3c")
+            .build();
+
+        let Err(err) = result else {
+            panic!("expected error for unknown propagator");
+        };
+        assert!(err.to_string().contains("\"w3c\""));
+    }
+}
@@ -0,0 +1,173 @@
+use opentelemetry::{
+    Context,
+    propagation::{
+        Extractor, Injector, TextMapPropagator, text_map_propagator::FieldIter,
+    },
+    trace::{SpanContext, TraceContextExt, TraceFlags, TraceState},
+};
+use std::sync::LazyLock;
+
+use super::{
+    extract_prefixed_baggage, inject_prefixed_baggage, parse_span_id, parse_trace_id,
+};
+
+const TRACE_ID_HEADER: &str = "ot-tracer-traceid";
+const SPAN_ID_HEADER: &str = "ot-tracer-spanid";
+const SAMPLED_HEADER: &str = "ot-tracer-sampled";
+const BAGGAGE_PREFIX: &str = "ot-baggage-";
+
+static OT_TRACE_HEADER_FIELDS: LazyLock<[String; 3]> = LazyLock::new(|| {
+    [
+        TRACE_ID_HEADER.to_owned(),
+        SPAN_ID_HEADER.to_owned(),
+        SAMPLED_HEADER.to_owned(),
+    ]
+});
+
+/// Propagator for the OpenTracing `ot-tracer-*` headers.
+///
+/// Trace context is propagated in the `ot-tracer-traceid`, `ot-tracer-spanid` and
+/// `ot-tracer-sampled` headers, baggage in `ot-baggage-{key}` headers.
+///
+/// The full 128-bit trace id is injected, so that it is preserved through OpenTracing
+/// hops. Both 64-bit and 128-bit trace ids are extracted.
+///
+/// Enabled with `ottrace` in `OTEL_PROPAGATORS`.
+#[derive(Debug, Clone, Default)]
+pub struct OtTracePropagator {
+    _private: (),
+}
+
+impl OtTracePropagator {
+    /// Creates a new OpenTracing propagator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn extract_span_context(&self, extractor: &dyn Extractor) -> Option<SpanContext> {
+        let trace_id = parse_trace_id(extractor.get(TRACE_ID_HEADER)?.trim())?;
+        let span_id = parse_span_id(extractor.get(SPAN_ID_HEADER)?.trim())?;
+        let trace_flags = match extractor.get(SAMPLED_HEADER).map(str::trim) {
+            Some(sampled) if sampled.eq_ignore_ascii_case("true") || sampled == "1" => {
+                TraceFlags::SAMPLED
+            }
+            _ => TraceFlags::default(),
+        };
+
+        let span_context =
+            SpanContext::new(trace_id, span_id, trace_flags, true, TraceState::default());
+        span_context.is_valid().then_some(span_context)
+    }
+}
+
+impl TextMapPropagator for OtTracePropagator {
+    fn inject_context(&self, cx: &Context, injector: &mut dyn Injector) {
+        let span = cx.span();
+        let span_context = span.span_context();
+        if span_context.is_valid() {
+            injector.set(TRACE_ID_HEADER, span_context.trace_id().to_string());
+            injector.set(SPAN_ID_HEADER, span_context.span_id().to_string());
+            injector.set(SAMPLED_HEADER, span_context.is_sampled().to_string());
+        }
+
+        inject_prefixed_baggage(cx, injector, BAGGAGE_PREFIX);
+    }
+
+    fn extract_with_context(&self, cx: &Context, extractor: &dyn Extractor) -> Context {
+        let cx = match self.extract_span_context(extractor) {
+            Some(span_context) => cx.with_remote_span_context(span_context),
+            None => cx.clone(),
+        };
+
+        extract_prefixed_baggage(&cx, extractor, BAGGAGE_PREFIX)
+    }
+
+    fn fields(&self) -> FieldIter<'_> {
+        FieldIter::new(OT_TRACE_HEADER_FIELDS.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert2::assert;
+    use opentelemetry::{
+        KeyValue,
+        baggage::BaggageExt,
+        trace::{SpanId, TraceId},
+    };
+    use rstest::rstest;
+    use std::collections::HashMap;
+
+    #[rstest]
+    #[case("4bf92f3577b34da6a3ce929d0e0e4736", "true", Some(("4bf92f3577b34da6a3ce929d0e0e4736", true)))]
+    #[case("a3ce929d0e0e4736", "1", Some(("0000000000000000a3ce929d0e0e4736", true)))]
+    #[case("a3ce929d0e0e4736", "false", Some(("0000000000000000a3ce929d0e0e4736", false)))]
+    #[case("0", "true", None)]
+    #[case("not-hex", "true", None)]
+    fn test_extract_ot_tracer_headers(
+        #[case] trace_id: &str,
+        #[case] sampled: &str,
+        #[case] expected: Option<(&str, bool)>,
+    ) {
+        let headers = HashMap::from([
+            (TRACE_ID_HEADER.to_owned(), trace_id.to_owned()),
+            (SPAN_ID_HEADER.to_owned(), "00f067aa0ba902b7".to_owned()),
+            (SAMPLED_HEADER.to_owned(), sampled.to_owned()),
+        ]);
+        let cx = OtTracePropagator::new().extract(&headers);
+        let span = cx.span();
+        let span_context = span.span_context();
+
+        match expected {
+            Some((trace_id, sampled)) => {
+                assert!(span_context.trace_id() == TraceId::from_hex(trace_id).unwrap());
+                assert!(span_context.is_sampled() == sampled);
+            }
+            None => assert!(!span_context.is_valid()),
+        }
+    }
+
+    #[test]
+    fn test_inject_ot_tracer_headers() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new()
+            .with_remote_span_context(span_context)
+            .with_baggage([KeyValue::new("tenant", "acme")]);
+
+        let mut headers = HashMap::new();
+        OtTracePropagator::new().inject_context(&cx, &mut headers);
+
+        assert!(headers[TRACE_ID_HEADER] == "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert!(headers[SPAN_ID_HEADER] == "00f067aa0ba902b7");
+        assert!(headers[SAMPLED_HEADER] == "true");
+        assert!(headers["ot-baggage-tenant"] == "acme");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let propagator = OtTracePropagator::new();
+
+        let mut headers = HashMap::new();
+        propagator.inject_context(
+            &Context::new().with_remote_span_context(span_context.clone()),
+            &mut headers,
+        );
+        let cx = propagator.extract(&headers);
+
+        assert!(*cx.span().span_context() == span_context);
+    }
+}
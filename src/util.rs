@@ -1,5 +1,3 @@
-use crate::{Key, KeyValue, Value};
-
 #[inline]
 pub(crate) fn env_var(key: &str) -> Option<String> {
     match std::env::var(key) {
@@ -23,8 +21,8 @@ pub(crate) fn env_var(key: &str) -> Option<String> {
 ))]
 #[inline]
 pub(crate) fn as_attribute(
-    key: impl Into<Key>,
-    maybe_value: Option<impl Into<Value>>,
-) -> Option<KeyValue> {
-    maybe_value.map(|value| KeyValue::new(key, value))
+    key: impl Into<crate::Key>,
+    maybe_value: Option<impl Into<crate::Value>>,
+) -> Option<crate::KeyValue> {
+    maybe_value.map(|value| crate::KeyValue::new(key, value))
 }